@@ -0,0 +1,49 @@
+mod common;
+
+use common::mid_game;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use polis_and_siege::ai::computer_turn::plan_turn;
+use polis_and_siege::ai::possible_move::{possible_moves, SearchBudget};
+use polis_and_siege::app_state::GameMode;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+
+fn search(c: &mut Criterion) {
+    let (world_state, world_fixed) = mid_game();
+    let thinking_time = world_state.get_current_player().profile.thinking_time;
+
+    let mut group = c.benchmark_group("possible_moves");
+    group.sample_size(10);
+    let mut placement = world_state.clone();
+    placement.mode = GameMode::ArmyPlacement;
+    group.bench_function("army placement", |b| {
+        b.iter(|| {
+            let budget = SearchBudget::new(thinking_time, Arc::new(AtomicUsize::new(0)));
+            possible_moves(&placement, &world_fixed, GameMode::ArmyPlacement, &budget)
+        })
+    });
+    group.bench_function("game", |b| {
+        b.iter(|| {
+            let budget = SearchBudget::new(thinking_time, Arc::new(AtomicUsize::new(0)));
+            possible_moves(&world_state, &world_fixed, GameMode::Game, &budget)
+        })
+    });
+    group.finish();
+}
+
+fn turn(c: &mut Criterion) {
+    let (world_state, world_fixed) = mid_game();
+    let mut group = c.benchmark_group("computer turn");
+    group.sample_size(10);
+    group.bench_function("plan_turn", |b| {
+        b.iter_batched(
+            || world_state.clone(),
+            |world_state| plan_turn(world_state, &world_fixed, &Arc::new(AtomicUsize::new(0))),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, search, turn);
+criterion_main!(benches);
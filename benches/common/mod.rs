@@ -0,0 +1,50 @@
+use polis_and_siege::app_state::{create_players, GameMode};
+use polis_and_siege::lib::cbor::{build_world, read_territories, CITIES_PER_TERRITORY};
+use polis_and_siege::model::profile::Difficulty;
+use polis_and_siege::model::setup::{GameSetup, MINIMUM_ALLOWED_DISTANCE};
+use polis_and_siege::model::world_fixed::WorldFixed;
+use polis_and_siege::model::world_state::WorldState;
+use rand::Rng;
+use std::time::Duration;
+
+pub const SEED: u64 = 1234;
+pub const PLAYERS: usize = 5;
+const GOLD: u32 = 12;
+
+// Players ready and cities chosen, but nobody owns anything yet
+pub fn new_world() -> (WorldState, WorldFixed) {
+    let mut world_state = WorldState::default();
+    world_state.reseed(SEED);
+    let mut world_fixed = WorldFixed::default();
+    create_players(&mut world_state, &mut world_fixed, &GameSetup::new(PLAYERS, Difficulty::Normal));
+    build_world(
+        &mut world_state,
+        &mut world_fixed,
+        read_territories(0),
+        CITIES_PER_TERRITORY,
+        MINIMUM_ALLOWED_DISTANCE,
+    );
+    (world_state, world_fixed)
+}
+
+// Cities dealt out round the table and garrisoned as they might be a few rounds in
+pub fn mid_game() -> (WorldState, WorldFixed) {
+    let (mut world_state, world_fixed) = new_world();
+    let mut player_index = 0;
+    while let Some(city_index) = world_state.cities_to_assign.pop() {
+        let armies = world_state.rng.random_range(1..=8);
+        let city = &mut world_state.cities[city_index];
+        city.owner = Some(player_index);
+        city.armies = armies;
+        player_index = (player_index + 1) % PLAYERS;
+    }
+    for player in world_state.players.iter_mut() {
+        player.gold = GOLD;
+
+        // Search to full depth every time, so runs measure the same amount of work
+        player.profile.thinking_time = Duration::from_secs(600);
+    }
+    world_state.mode = GameMode::Game;
+    world_state.update_scores(&world_fixed);
+    (world_state, world_fixed)
+}
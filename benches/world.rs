@@ -0,0 +1,81 @@
+mod common;
+
+use common::{mid_game, PLAYERS, SEED};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use polis_and_siege::app_state::create_players;
+use polis_and_siege::lib::cbor::{read_territories, CITIES_PER_TERRITORY};
+use polis_and_siege::model::city::select_evenly_spaced_cities;
+use polis_and_siege::model::connection::build_connections;
+use polis_and_siege::model::profile::Difficulty;
+use polis_and_siege::model::setup::{GameSetup, MINIMUM_ALLOWED_DISTANCE};
+use polis_and_siege::model::world_fixed::WorldFixed;
+use polis_and_siege::model::world_state::WorldState;
+use std::hint::black_box;
+
+fn import(c: &mut Criterion) {
+    c.bench_function("cbor::read_territories", |b| b.iter(|| read_territories(0)));
+}
+
+fn city_selection(c: &mut Criterion) {
+    c.bench_function("select_evenly_spaced_cities", |b| {
+        b.iter_batched(
+            || {
+                let mut world_state = WorldState::default();
+                world_state.reseed(SEED);
+                let mut world_fixed = WorldFixed::default();
+                create_players(&mut world_state, &mut world_fixed, &GameSetup::new(PLAYERS, Difficulty::Normal));
+                (world_state, world_fixed, read_territories(0))
+            },
+            |(mut world_state, mut world_fixed, territories)| {
+                select_evenly_spaced_cities(
+                    &mut world_state,
+                    &mut world_fixed,
+                    MINIMUM_ALLOWED_DISTANCE,
+                    CITIES_PER_TERRITORY,
+                    territories,
+                );
+                (world_state, world_fixed)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn connections(c: &mut Criterion) {
+    c.bench_function("build_connections", |b| {
+        b.iter_batched(
+            || {
+                let mut world_state = WorldState::default();
+                let mut world_fixed = WorldFixed::default();
+                select_evenly_spaced_cities(
+                    &mut world_state,
+                    &mut world_fixed,
+                    MINIMUM_ALLOWED_DISTANCE,
+                    CITIES_PER_TERRITORY,
+                    read_territories(0),
+                );
+                world_fixed
+            },
+            |mut world_fixed| {
+                build_connections(&mut world_fixed);
+                world_fixed
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn world_state(c: &mut Criterion) {
+    let (mut world_state, world_fixed) = mid_game();
+    c.bench_function("WorldState::clone", |b| b.iter(|| black_box(&world_state).clone()));
+    c.bench_function("update_scores", |b| b.iter(|| world_state.update_scores(black_box(&world_fixed))));
+
+    // A single city changing hands, as after an attack
+    let city_index = world_fixed.cities.len() / 2;
+    c.bench_function("update_scores_after", |b| {
+        b.iter(|| world_state.update_scores_after(black_box(&world_fixed), &[city_index]))
+    });
+}
+
+criterion_group!(benches, import, city_selection, connections, world_state);
+criterion_main!(benches);
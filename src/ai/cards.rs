@@ -0,0 +1,36 @@
+use crate::model::card::{all_sets, MAXIMUM_HAND};
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+
+// Prefer sets naming cities we hold, as they also reinforce those cities
+fn best_set(world_state: &WorldState, current_player: usize) -> Option<[usize; 3]> {
+    let player = world_state.get_player_for_index(current_player);
+    all_sets(&player.cards).into_iter().max_by_key(|set| {
+        set.iter()
+            .filter(|card_index| match player.cards[**card_index].city {
+                Some(city_index) => world_state.cities[city_index].owner == Some(current_player),
+                None => false,
+            })
+            .count()
+    })
+}
+
+// Chosen on what the player can see, each set traded on that and for real
+pub fn ai_trade_cards(world_state: &mut WorldState, world_fixed: &WorldFixed) {
+    let current_player = world_state.get_current_player_index();
+    let mut view = world_state.as_seen_by(world_fixed, current_player);
+
+    // Trade straight away if under threat, otherwise hold on until the hand is full
+    let bordering_enemy = (0..view.cities.len()).any(|city_index| {
+        view.cities[city_index].owner == Some(current_player) && view.borders_enemy(world_fixed, city_index)
+    });
+    let hand_size = view.get_current_player().cards.len();
+    if !bordering_enemy && hand_size < MAXIMUM_HAND {
+        return;
+    }
+
+    while let Some(set) = best_set(&view, current_player) {
+        view.trade_cards(current_player, set);
+        world_state.trade_cards(current_player, set);
+    }
+}
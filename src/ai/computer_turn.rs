@@ -1,55 +1,194 @@
-use crate::ai::possible_move::possible_moves;
+use crate::ai::cards::ai_trade_cards;
+use crate::ai::diplomacy::ai_propose;
+use crate::ai::moves::Move;
+use crate::ai::planner::{attack_value, plan_objective};
+use crate::ai::possible_move::{possible_moves, SearchBudget};
+use crate::ai::purchase::ai_purchase;
+use crate::ai::worker::{AiRequest, AiWorker};
 use crate::app_state::{AppState, GameMode};
+use crate::camera::follow;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::{Visibility, WorldState};
 use crate::next_turn;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-pub fn computer_turn_by_phase(app_state: &mut AppState, mode: GameMode) {
-    let mut possibles = possible_moves(&app_state.world_state, &mut app_state.world_fixed, 0, mode.clone());
-    if mode == GameMode::Game {
-        println!("Attacks: {}", possibles.len());
+// Pause between each planned move so they can be followed on screen
+pub const MOVE_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Default)]
+pub enum AiTurn {
+    #[default]
+    Idle,
+    Thinking {
+        started: Instant,
+        nodes: Arc<AtomicUsize>,
+        plan: Receiver<Vec<Move>>,
+    },
+    Playing {
+        moves: VecDeque<Move>,
+        current: Option<Move>,
+        since: Instant,
+    },
+}
+
+impl AiTurn {
+    // Positions searched and time taken so far, while the worker is busy
+    pub fn progress(&self) -> Option<(usize, Duration)> {
+        match self {
+            AiTurn::Thinking { started, nodes, .. } => Some((nodes.load(Ordering::Relaxed), started.elapsed())),
+            _ => None,
+        }
+    }
+
+    // Move being played out and how far through its animation we are
+    pub fn current_move(&self) -> Option<(&Move, f32)> {
+        match self {
+            AiTurn::Playing { current: Some(current), since, .. } => {
+                Some((current, (since.elapsed().as_secs_f32() / MOVE_DELAY.as_secs_f32()).min(1.0)))
+            }
+            _ => None,
+        }
     }
+}
+
+// Search one phase on a copy of the world, placements are made on the copy as they're chosen
+fn plan_phase(
+    world_state: &mut WorldState,
+    world_fixed: &WorldFixed,
+    mode: GameMode,
+    nodes: &Arc<AtomicUsize>,
+) -> Vec<Move> {
+    // Only plan with what we can see
+    let player_index = world_state.get_current_player_index();
+    let view = world_state.as_seen_by(world_fixed, player_index);
+    let budget = SearchBudget::new(view.get_current_player().profile.thinking_time, nodes.clone());
+    let mut possibles = possible_moves(&view, world_fixed, mode.clone(), &budget);
+    debug_log!("Searched {} positions, {} transpositions", budget.nodes(), budget.hits());
 
+    let mut plan = Vec::new();
     if possibles.is_empty() {
-        println!("no possible {:?} moves", mode);
+        debug_log!("no possible {:?} moves", mode);
         //app_state.world_state.mode = GameMode::End;
-        return;
-    } else {
-        print!("there are {} possible {:?} moves, ", possibles.len(), mode);
+        return plan;
     }
 
     // Score range
     let lowest = possibles.iter().min_by_key(|p| p.score_portion).unwrap().score_portion;
     let highest = possibles.iter().max_by_key(|p| p.score_portion).unwrap().score_portion;
-    println!("lowest and highest score: {}/{}", lowest, highest);
+    debug_log!(
+        "there are {} possible {:?} moves, lowest and highest score: {}/{}",
+        possibles.len(),
+        mode,
+        lowest,
+        highest
+    );
 
     // Select move(s)
     possibles.sort_by(|a, b| a.score_portion.cmp(&b.score_portion));
     //    println!("{:#?}", possibles);
 
-    let world_state = &mut app_state.world_state;
     match mode {
         GameMode::ArmyPlacement => {
-            while !possibles.is_empty() && world_state.current_player.as_ref().unwrap().borrow().armies_to_assign > 0 {
+            while !possibles.is_empty() && world_state.get_current_player().armies_to_assign > 0 {
                 let the_move = possibles.pop().unwrap();
-                the_move.do_move(world_state);
-            }
-        }
-        GameMode::Game => {
-            for the_move in possibles {
-                the_move.do_move(world_state);
+                the_move.do_move(world_state, world_fixed);
+                plan.push(the_move);
             }
         }
+        GameMode::Game => plan.extend(possibles),
         _ => {}
     }
+    plan
+}
+
+// Runs on the worker thread, works out the whole turn from a snapshot
+pub fn plan_turn(mut world_state: WorldState, world_fixed: &WorldFixed, nodes: &Arc<AtomicUsize>) -> Vec<Move> {
+    let player = world_state.get_current_player();
+    debug_log!("Starting score: {}, {} armies to place", player.score, player.armies_to_assign);
+
+    // Aim for a territory if there's one in reach, otherwise take the best move at a time from the search. Only
+    // once the game proper is under way, before then there's nothing to attack with
+    if world_state.mode == GameMode::Game {
+        let mut view = world_state.as_seen_by(world_fixed, world_state.get_current_player_index());
+        if let Some(plan) = plan_objective(&mut view, world_fixed, nodes) {
+            return plan;
+        }
+    }
+    let mut plan = Vec::new();
+    if player.armies_to_assign > 0 {
+        plan.extend(plan_phase(&mut world_state, world_fixed, GameMode::ArmyPlacement, nodes));
+    }
+    plan.extend(plan_phase(&mut world_state, world_fixed, GameMode::Game, nodes));
+    plan
+}
+
+// The dice may have gone differently for real, so drop attacks that no longer pay off or can't be made
+fn next_worthwhile(app_state: &AppState, moves: &mut VecDeque<Move>) -> Option<Move> {
+    let player_index = app_state.world_state.get_current_player_index();
+    while let Some(next) = moves.pop_front() {
+        if !next.is_attack() {
+            return Some(next);
+        }
+        // Planned slips go ahead anyway, they were never meant to pay
+        let view = app_state.world_state.as_seen_by(&app_state.world_fixed, player_index);
+        match attack_value(&view, &app_state.world_fixed, &next) {
+            Some(value) if value > 0.0 || next.score_portion <= 0 => return Some(next),
+            _ => {}
+        }
+    }
+    None
 }
 
-pub fn computer_turn(app_state: &mut AppState) {
-    // Get current player
-    let player = app_state.world_state.current_player.as_ref().unwrap();
-    print!("Starting score: {}, {} armies to place, ", player.borrow().score, player.borrow().armies_to_assign);
-    if player.borrow().armies_to_assign > 0 {
-        computer_turn_by_phase(app_state, GameMode::ArmyPlacement);
+// Take the view to where the move's happening, if whoever's watching could see it
+fn follow_move(app_state: &mut AppState, the_move: &Move) {
+    let Some(city_index) = the_move.city_target.or(the_move.city_source) else {
+        return;
+    };
+    let visibility = app_state.world_state.visibility(&app_state.world_fixed, app_state.viewing_player());
+    if visibility[city_index] != Visibility::Hidden {
+        follow(app_state, app_state.world_fixed.cities[city_index].location.p);
     }
-    computer_turn_by_phase(app_state, GameMode::Game);
+}
+
+// Called every frame while it's the computer's go, never blocks
+pub fn computer_turn(app_state: &mut AppState, worker: &AiWorker) {
+    app_state.ai_turn = match std::mem::take(&mut app_state.ai_turn) {
+        AiTurn::Idle => {
+            // Make any offers, cash in any cards and spend gold first, then hand the rest over to the worker
+            if app_state.world_state.mode == GameMode::Game {
+                ai_propose(&mut app_state.world_state, &app_state.world_fixed);
+            }
+            ai_trade_cards(&mut app_state.world_state, &app_state.world_fixed);
+            ai_purchase(&mut app_state.world_state, &app_state.world_fixed);
 
-    next_turn(app_state);
+            let (reply, plan) = channel();
+            let nodes = Arc::new(AtomicUsize::new(0));
+            worker.request(AiRequest { world_state: app_state.world_state.clone(), nodes: nodes.clone(), reply });
+            AiTurn::Thinking { started: Instant::now(), nodes, plan }
+        }
+        AiTurn::Thinking { started, nodes, plan } => match plan.try_recv() {
+            Ok(moves) => {
+                debug_log!("Planned {} moves in {:.2}s", moves.len(), started.elapsed().as_secs_f32());
+                AiTurn::Playing { moves: moves.into(), current: None, since: Instant::now() }
+            }
+            Err(TryRecvError::Empty) => AiTurn::Thinking { started, nodes, plan },
+            Err(TryRecvError::Disconnected) => panic!("AI worker has stopped"),
+        },
+        AiTurn::Playing { mut moves, current, since } => {
+            if current.is_some() && since.elapsed() < MOVE_DELAY {
+                AiTurn::Playing { moves, current, since }
+            } else if let Some(next) = next_worthwhile(app_state, &mut moves) {
+                next.do_move(&mut app_state.world_state, &app_state.world_fixed);
+                follow_move(app_state, &next);
+                AiTurn::Playing { moves, current: Some(next), since: Instant::now() }
+            } else {
+                next_turn(app_state);
+                AiTurn::Idle
+            }
+        }
+    };
 }
@@ -0,0 +1,27 @@
+use crate::ai::moves::Move;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+
+// Develop one safe interior city a turn, best value for money first
+pub fn best_upgrade(world_state: &WorldState, world_fixed: &WorldFixed) -> Option<Move> {
+    let current_player = world_state.get_current_player_index();
+    let profile = world_state.get_current_player().profile;
+    let gold = world_state.get_current_player().gold;
+
+    let mut best: Option<(usize, f32)> = None;
+    for (city_index, city) in world_state.cities.iter().enumerate() {
+        if city.owner != Some(current_player)
+            || !city.can_upgrade()
+            || world_state.borders_enemy(world_fixed, city_index)
+            || gold < city.upgrade_cost()
+        {
+            continue;
+        }
+        let value = (profile.city_size_multiplier + world_fixed.cities[city_index].role.score(&profile))
+            / city.upgrade_cost() as f32;
+        if best.is_none() || value > best.unwrap().1 {
+            best = Some((city_index, value));
+        }
+    }
+    best.map(|(city_index, _)| Move::new_upgrade_city(city_index))
+}
@@ -0,0 +1,130 @@
+use crate::model::diplomacy::{Proposal, TreatyKind, TRUCE_LENGTHS};
+use crate::model::profile::Personality;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+
+// Share of the total score at which a player is running away with the game
+const RUNAWAY_SHARE: f32 = 0.4;
+
+// Connections running between two players' cities, the length of the front they'd be making peace on
+fn shared_border(world_state: &WorldState, world_fixed: &WorldFixed, a: usize, b: usize) -> usize {
+    world_state
+        .cities
+        .iter()
+        .enumerate()
+        .filter(|(_, city)| city.owner == Some(a))
+        .map(|(city_index, _)| {
+            world_fixed.cities[city_index]
+                .connections
+                .iter()
+                .filter(|connection| world_state.cities[connection.city2].owner == Some(b))
+                .count()
+        })
+        .sum()
+}
+
+// How strong the other player is next to us, above one they're the bigger
+fn relative_score(world_state: &WorldState, player_index: usize, other: usize) -> f32 {
+    let ours = world_state.players[player_index].score.max(1) as f32;
+    world_state.players[other].score.max(0) as f32 / ours
+}
+
+// Someone other than the two of them holding a big enough share of the score to be worth ganging up on
+fn runaway_leader(world_state: &WorldState, a: usize, b: usize) -> Option<usize> {
+    let total: i32 = world_state.players.iter().map(|player| player.score.max(0)).sum();
+    world_state
+        .players
+        .iter()
+        .filter(|player| player.index != a && player.index != b)
+        .max_by_key(|player| player.score)
+        .filter(|leader| leader.score as f32 > total as f32 * RUNAWAY_SHARE)
+        .map(|leader| leader.index)
+}
+
+// The longest truce a personality will tie itself to, and so what it offers. Turtles want a long peace, the
+// rest want to be free to attack again soon
+fn truce_length(personality: Personality) -> u32 {
+    match personality {
+        Personality::Turtle => TRUCE_LENGTHS[2],
+        Personality::Aggressor | Personality::Opportunist => TRUCE_LENGTHS[0],
+        Personality::Balanced | Personality::TerritoryHoarder => TRUCE_LENGTHS[1],
+    }
+}
+
+// Would the player the offer is made to take it, judging by what they can see? A truce buys time against
+// someone at least as strong on a shared front, an alliance is only worth it against a common threat
+pub fn ai_consider(world_state: &WorldState, world_fixed: &WorldFixed, proposal: &Proposal) -> bool {
+    let world_state = &world_state.as_seen_by(world_fixed, proposal.to);
+    let profile = &world_state.players[proposal.to].profile;
+    let relative = relative_score(world_state, proposal.to, proposal.from);
+    let border = shared_border(world_state, world_fixed, proposal.to, proposal.from);
+    if proposal.kind == TreatyKind::Truce && proposal.turns > truce_length(profile.personality) {
+        return false;
+    }
+    match (proposal.kind, profile.personality) {
+        (TreatyKind::Truce, Personality::Aggressor) => relative > 1.5 && border > 0,
+        (TreatyKind::Truce, Personality::Turtle) => relative > 0.5,
+        (TreatyKind::Truce, _) => relative > 0.8 && border > 0,
+        (TreatyKind::Alliance, Personality::Aggressor) => false,
+        (TreatyKind::Alliance, _) => {
+            runaway_leader(world_state, proposal.from, proposal.to).is_some() && (0.5..2.0).contains(&relative)
+        }
+    }
+}
+
+// At the start of its turn, offer an alliance against a runaway leader or else a truce to the strongest
+// neighbour that outscores us, as far as we can see. Other computers answer straight away, humans get it in
+// the diplomacy panel
+pub fn ai_propose(world_state: &mut WorldState, world_fixed: &WorldFixed) {
+    let player_index = world_state.get_current_player_index();
+    if world_state.get_current_player().profile.personality == Personality::Aggressor {
+        return;
+    }
+
+    let view = world_state.as_seen_by(world_fixed, player_index);
+    let mut best: Option<(usize, TreatyKind, f32)> = None;
+    for other in 0..view.players.len() {
+        let diplomacy = &view.diplomacy;
+        if other == player_index
+            || diplomacy.allied(player_index, other)
+            || diplomacy.proposal(player_index, other).is_some()
+            || diplomacy.proposal(other, player_index).is_some()
+        {
+            continue;
+        }
+        let relative = relative_score(&view, player_index, other);
+        let kind = if runaway_leader(&view, player_index, other).is_some() {
+            TreatyKind::Alliance
+        } else if relative > 1.0
+            && !diplomacy.at_peace(player_index, other)
+            && shared_border(&view, world_fixed, player_index, other) > 0
+        {
+            TreatyKind::Truce
+        } else {
+            continue;
+        };
+        if best.is_none_or(|(_, _, best_relative)| relative > best_relative) {
+            best = Some((other, kind, relative));
+        }
+    }
+    let Some((other, kind, _)) = best else {
+        return;
+    };
+
+    let turns = truce_length(world_state.get_current_player().profile.personality);
+    world_state.diplomacy.propose(player_index, other, kind, turns);
+    if world_state.players[other].is_human() {
+        debug_log!("Player {} offers player {} a {}", player_index, other, kind.name());
+        return;
+    }
+    let proposal = *world_state.diplomacy.proposal(player_index, other).unwrap();
+    let accept = ai_consider(world_state, world_fixed, &proposal);
+    debug_log!(
+        "Player {} offers player {} a {}, {}",
+        player_index,
+        other,
+        kind.name(),
+        if accept { "accepted" } else { "declined" }
+    );
+    world_state.diplomacy.answer(player_index, other, accept);
+}
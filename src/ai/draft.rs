@@ -0,0 +1,71 @@
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+use rand::Rng;
+
+// Cities of a territory with a connection out of it, the ways in that need holding
+fn entry_points(world_fixed: &WorldFixed, territory_name: &str) -> usize {
+    let territory = world_fixed.territories.get(territory_name).unwrap();
+    territory
+        .cities
+        .iter()
+        .filter(|city_index| {
+            world_fixed.cities[**city_index]
+                .connections
+                .iter()
+                .any(|connection| world_fixed.cities[connection.city2].territory_name != territory_name)
+        })
+        .count()
+}
+
+// What an unowned city is worth to the player picking, its own value plus how it builds towards a whole
+// territory and how easily that territory could be held
+fn draft_value(world_state: &WorldState, world_fixed: &WorldFixed, player_index: usize, city_index: usize) -> f32 {
+    let profile = &world_state.get_player_for_index(player_index).profile;
+    let city = &world_state.cities[city_index];
+    let statics = &world_fixed.cities[city_index];
+    let mut value = city.size as f32 * profile.city_size_multiplier + statics.role.score(profile);
+
+    // Concentration, worth more the more of the territory we'd have, much less once someone else is in it
+    let territory = world_fixed.territories.get(&statics.territory_name).unwrap();
+    let ours = territory.cities.iter().filter(|other| world_state.cities[**other].owner == Some(player_index)).count();
+    let contested = territory
+        .cities
+        .iter()
+        .any(|other| world_state.cities[*other].owner.is_some_and(|owner| owner != player_index));
+    let share = (ours + 1) as f32 / territory.cities.len() as f32;
+    value += profile.territory_value * share * if contested { 0.25 } else { 1.0 };
+
+    // Chokepoints, a way into a territory with few of them is worth holding
+    let gateway = statics
+        .connections
+        .iter()
+        .any(|connection| world_fixed.cities[connection.city2].territory_name != statics.territory_name);
+    if gateway {
+        value += profile.army_bordering / entry_points(world_fixed, &statics.territory_name).max(1) as f32;
+    }
+
+    // Next to our own cities is easier to defend
+    let neighbours = statics
+        .connections
+        .iter()
+        .filter(|connection| world_state.cities[connection.city2].owner == Some(player_index));
+    value + neighbours.count() as f32 * profile.army_same_territory
+}
+
+// The current player's pick of the cities nobody has yet, now and then any old one if it's prone to slips
+pub fn draft_pick(world_state: &mut WorldState, world_fixed: &WorldFixed) -> Option<usize> {
+    let player_index = world_state.get_current_player_index();
+    if world_state.cities_to_assign.is_empty() {
+        return None;
+    }
+    let chance = world_state.get_current_player().profile.mistake_chance;
+    if chance > 0.0 && world_state.rng.random::<f32>() < chance {
+        let pick = world_state.rng.random_range(0..world_state.cities_to_assign.len());
+        return Some(world_state.cities_to_assign[pick]);
+    }
+    world_state.cities_to_assign.iter().copied().max_by(|a, b| {
+        let a_value = draft_value(world_state, world_fixed, player_index, *a);
+        let b_value = draft_value(world_state, world_fixed, player_index, *b);
+        a_value.total_cmp(&b_value)
+    })
+}
@@ -1,18 +1,47 @@
 use crate::ai::moves::Move;
+use crate::model::world_fixed::WorldFixed;
 use crate::model::world_state::WorldState;
 
-pub fn game_build_list_of_possibles(world_state: &WorldState, current_player: usize) -> Vec<Move> {
+// Enemy cities cavalry can reach, treaty partners aside, riding through at most one other city
+pub fn two_hop_targets(world_state: &WorldState, world_fixed: &WorldFixed, source: usize) -> Vec<usize> {
+    let mut results = Vec::new();
+    for first in world_fixed.cities[source].connections.iter() {
+        if world_state.can_attack(source, first.city2) && !results.contains(&first.city2) {
+            results.push(first.city2);
+        }
+        for second in world_fixed.cities[first.city2].connections.iter() {
+            if second.city2 != source
+                && world_state.can_attack(source, second.city2)
+                && !results.contains(&second.city2)
+            {
+                results.push(second.city2);
+            }
+        }
+    }
+    results
+}
+
+pub fn game_build_list_of_possibles(
+    world_state: &WorldState,
+    world_fixed: &WorldFixed,
+    current_player: usize,
+) -> Vec<Move> {
     let mut results: Vec<Move> = Vec::new();
-    let player = world_state.get_player_for_index(current_player);
-    for city in world_state.cities.iter() {
-        if city.borrow().owner.unwrap() == current_player {
-            // Are there any enemy cities connected to attack?
-            for connection in city.borrow().statics.borrow().connections.iter() {
-                let other_city = &world_state.cities[connection.city2];
-                if other_city.borrow().owner.unwrap() != current_player {
+    for (city_index, city) in world_state.cities.iter().enumerate() {
+        if city.owner == Some(current_player) {
+            // Are there any enemy cities connected to attack? Treaty partners are off limits
+            for connection in world_fixed.cities[city_index].connections.iter() {
+                if world_state.can_attack(connection.city1, connection.city2) {
                     results.push(Move::new_attack_city(connection.city1, connection.city2));
                 }
             }
+
+            // Cavalry can strike further afield
+            if city.cavalry > 0 {
+                for target in two_hop_targets(world_state, world_fixed, city_index) {
+                    results.push(Move::new_cavalry_attack(city_index, target));
+                }
+            }
         }
     }
     results
@@ -1,23 +1,39 @@
 use crate::ai::army_placement::MAXIMUM_ARMIES_PER_CITY;
+use crate::model::economy::{CAVALRY_COST, FORTIFY_COST, MAXIMUM_CAVALRY_PER_CITY, REGIMENT_COST};
+use crate::model::world_fixed::WorldFixed;
 use crate::model::world_state::WorldState;
 use rand::Rng;
 use std::fmt;
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum MoveType {
     #[default]
     PlaceArmy,
     AttackCity,
+    UpgradeCity,
+    BuyRegiment,
+    FortifyCity,
+    BuyCavalry,
+    CavalryAttack,
 }
 
-#[derive(Default)]
+// What came of trying a move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveResult {
+    // Not allowed or not affordable, nothing changed
+    Refused,
+    Done,
+    // Dice were rolled, so it can't be taken back
+    Rolled,
+}
+
+#[derive(Default, Clone)]
 pub struct Move {
     pub move_type: MoveType,
     pub city_source: Option<usize>,
     pub city_target: Option<usize>,
     pub child_moves: Vec<Move>,
     pub score_portion: i32,
-    pub world_state: WorldState,
 }
 
 impl fmt::Debug for Move {
@@ -43,93 +59,193 @@ impl Move {
     }
 
     pub fn new_place_army(city_source: usize) -> Self {
+        Self { move_type: MoveType::PlaceArmy, city_source: Some(city_source), ..Default::default() }
+    }
+
+    pub fn new_upgrade_city(city_source: usize) -> Self {
+        Self { move_type: MoveType::UpgradeCity, city_source: Some(city_source), ..Default::default() }
+    }
+
+    pub fn new_buy_regiment() -> Self {
+        Self { move_type: MoveType::BuyRegiment, ..Default::default() }
+    }
+
+    pub fn new_fortify_city(city_source: usize) -> Self {
+        Self { move_type: MoveType::FortifyCity, city_source: Some(city_source), ..Default::default() }
+    }
+
+    pub fn new_buy_cavalry(city_source: usize) -> Self {
+        Self { move_type: MoveType::BuyCavalry, city_source: Some(city_source), ..Default::default() }
+    }
+
+    pub fn new_cavalry_attack(city_source: usize, city_target: usize) -> Self {
         Self {
-            move_type: MoveType::PlaceArmy,
+            move_type: MoveType::CavalryAttack,
             city_source: Some(city_source),
+            city_target: Some(city_target),
             ..Default::default()
         }
     }
 
-    pub fn do_move(&self, world_state: &mut WorldState) {
-        let player = world_state.get_current_player();
+    pub fn is_attack(&self) -> bool {
+        matches!(self.move_type, MoveType::AttackCity | MoveType::CavalryAttack)
+    }
+
+    // Cities whose state the move can change
+    pub fn touched_cities(&self) -> Vec<usize> {
+        self.city_source.into_iter().chain(self.city_target).collect()
+    }
+
+    pub fn do_move(&self, world_state: &mut WorldState, world_fixed: &WorldFixed) -> MoveResult {
+        let player_index = world_state.get_current_player_index();
+        let gold = world_state.players[player_index].gold;
+        let mut result = MoveResult::Refused;
         match self.move_type {
             MoveType::PlaceArmy => {
-                let city = &world_state.cities[self.city_source.unwrap()];
-                if city.borrow().armies < MAXIMUM_ARMIES_PER_CITY {
-                    city.borrow_mut().armies += 1;
-                    player.borrow_mut().armies_to_assign -= 1;
+                let city = &mut world_state.cities[self.city_source.unwrap()];
+                if city.armies < MAXIMUM_ARMIES_PER_CITY {
+                    city.armies += 1;
+                    world_state.players[player_index].armies_to_assign -= 1;
+                    result = MoveResult::Done;
+                }
+            }
+            MoveType::UpgradeCity => {
+                let city = &mut world_state.cities[self.city_source.unwrap()];
+                let cost = city.upgrade_cost();
+                if city.can_upgrade() && gold >= cost {
+                    city.size += 1;
+                    city.growth = 0.0;
+                    world_state.players[player_index].gold -= cost;
+                    result = MoveResult::Done;
+                }
+            }
+            MoveType::BuyRegiment => {
+                if gold >= REGIMENT_COST {
+                    let player = &mut world_state.players[player_index];
+                    player.gold -= REGIMENT_COST;
+                    player.armies_to_assign += 1;
+                    result = MoveResult::Done;
+                }
+            }
+            MoveType::FortifyCity => {
+                let city = &mut world_state.cities[self.city_source.unwrap()];
+                if city.can_fortify() && gold >= FORTIFY_COST {
+                    city.fortification += 1;
+                    world_state.players[player_index].gold -= FORTIFY_COST;
+                    result = MoveResult::Done;
+                }
+            }
+            MoveType::BuyCavalry => {
+                let city = &mut world_state.cities[self.city_source.unwrap()];
+                if city.cavalry < MAXIMUM_CAVALRY_PER_CITY && gold >= CAVALRY_COST {
+                    city.cavalry += 1;
+                    world_state.players[player_index].gold -= CAVALRY_COST;
+                    result = MoveResult::Done;
                 }
             }
             MoveType::AttackCity => {
-                let mut rng = rand::rng();
                 let source = self.city_source.unwrap();
                 let target = self.city_target.unwrap();
+                let minimum_armies = world_state.players[player_index].profile.minimum_armies;
 
-                // Make sure we haven't already taken it and have enough armies
-                if world_state.cities[source].borrow().owner != world_state.cities[target].borrow().owner
-                    && world_state.cities[source].borrow().armies >= player.borrow().profile.minimum_armies
+                // Make sure it's our city, we haven't already taken the target, aren't bound by a treaty and have
+                // enough armies. A planned attack can come from a city we only took in simulation
+                let ours = world_state.cities[source].owner == Some(player_index);
+                if ours && world_state.can_attack(source, target) && world_state.cities[source].armies >= minimum_armies
                 {
-                    let mut source_armies = world_state.cities[source].borrow().armies - 1;
-                    let target_armies = world_state.cities[target].borrow().armies;
-
-                    // Roll dice
-                    let mut dice_source = Vec::new();
-                    let mut dice_target = Vec::new();
-
-                    // source dice
-                    for _i in 1..=source_armies {
-                        let dice = rng.random_range(1u8..=6u8);
-                        dice_source.push(dice);
-                    }
-
-                    // Target dice
-                    for _i in 1..=target_armies {
-                        let dice = rng.random_range(1u8..=6u8);
-                        dice_target.push(dice);
-                    }
-
-                    // Now order by
-                    dice_source.sort();
-                    dice_target.sort();
-
-                    // And compare each
-                    print!(
-                        "Attacking with {}/{} (out of {},{}), ",
+                    let source_armies = world_state.cities[source].armies - 1;
+                    world_state.cities[target].besieged = true;
+                    let target_armies = world_state.cities[target].armies;
+
+                    // Fortified cities defend better
+                    let defence_bonus = world_state.cities[target].defence_bonus(&world_fixed.cities[target]);
+
+                    let (source_losses, target_losses) =
+                        roll_battle(&mut world_state.rng, source_armies, target_armies, defence_bonus);
+                    world_state.cities[source].armies -= source_losses;
+                    world_state.cities[target].armies -= target_losses;
+                    debug_log!(
+                        "Attacking with {}/{}, after is {},{}.",
                         source_armies,
                         target_armies,
-                        world_state.cities[source].borrow().armies,
-                        world_state.cities[target].borrow().armies,
-                    );
-
-                    for i in 0..dice_source.len() {
-                        if i >= dice_target.len() || dice_source[i] > dice_target[i] {
-                            world_state.cities[target].borrow_mut().armies -= 1;
-                            if world_state.cities[target].borrow().armies == 0 {
-                                break;
-                            }
-                        } else {
-                            world_state.cities[source].borrow_mut().armies -= 1;
-                            source_armies -= 1;
-                        }
-                    }
-
-                    println!(
-                        "After is {},{}.",
-                        world_state.cities[source].borrow().armies,
-                        world_state.cities[target].borrow().armies
+                        world_state.cities[source].armies,
+                        world_state.cities[target].armies,
                     );
 
                     // Take over!
-                    if world_state.cities[target].borrow().armies == 0 {
-                        println!("City taken!");
+                    if world_state.cities[target].armies == 0 {
+                        take_city(world_state, source, target, source_armies - source_losses);
+                    }
+                    result = MoveResult::Rolled;
+                }
+            }
+            MoveType::CavalryAttack => {
+                let source = self.city_source.unwrap();
+                let target = self.city_target.unwrap();
 
-                        // Take the city
-                        let source_owner = world_state.cities[source].borrow().owner.unwrap();
-                        world_state.cities[target].borrow_mut().owner = Some(source_owner);
-                        world_state.cities[target].borrow_mut().armies = source_armies;
+                // Cavalry ride out on their own, the garrison stays at home
+                let cavalry = world_state.cities[source].cavalry;
+                let ours = world_state.cities[source].owner == Some(player_index);
+                if ours && world_state.can_attack(source, target) && cavalry > 0 {
+                    world_state.cities[target].besieged = true;
+                    let target_armies = world_state.cities[target].armies;
+                    let defence_bonus = world_state.cities[target].defence_bonus(&world_fixed.cities[target]);
+
+                    let (source_losses, target_losses) =
+                        roll_battle(&mut world_state.rng, cavalry, target_armies, defence_bonus);
+                    world_state.cities[source].cavalry = 0;
+                    world_state.cities[target].armies -= target_losses;
+                    let survivors = cavalry - source_losses;
+                    debug_log!("Cavalry raid with {}/{}, {} riders survive.", cavalry, target_armies, survivors);
+
+                    // Survivors garrison the city if taken, otherwise they ride home
+                    if world_state.cities[target].armies == 0 {
+                        take_city(world_state, source, target, survivors);
+                    } else {
+                        world_state.cities[source].cavalry = survivors;
                     }
+                    result = MoveResult::Rolled;
                 }
             }
         }
+        result
+    }
+}
+
+// Dice off lowest against lowest, returns the armies lost by each side
+pub fn roll_battle(
+    rng: &mut impl Rng,
+    source_armies: usize,
+    target_armies: usize,
+    defence_bonus: u8,
+) -> (usize, usize) {
+    let mut dice_source: Vec<u8> = (0..source_armies).map(|_| rng.random_range(1u8..=6u8)).collect();
+    let mut dice_target: Vec<u8> = (0..target_armies).map(|_| rng.random_range(1u8..=6u8) + defence_bonus).collect();
+    dice_source.sort();
+    dice_target.sort();
+
+    let mut source_losses = 0;
+    let mut target_losses = 0;
+    for i in 0..dice_source.len() {
+        if target_losses >= target_armies {
+            break;
+        }
+        if i >= dice_target.len() || dice_source[i] > dice_target[i] {
+            target_losses += 1;
+        } else {
+            source_losses += 1;
+        }
     }
+    (source_losses, target_losses)
+}
+
+fn take_city(world_state: &mut WorldState, source: usize, target: usize, armies: usize) {
+    debug_log!("City taken!");
+    let source_owner = world_state.cities[source].owner;
+    let city = &mut world_state.cities[target];
+    city.owner = source_owner;
+    city.armies = armies;
+    city.capture();
+    world_state.ownership_changes += 1;
+    world_state.get_current_player_mut().captured_this_turn = true;
 }
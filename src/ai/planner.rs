@@ -0,0 +1,341 @@
+use crate::ai::army_placement::MAXIMUM_ARMIES_PER_CITY;
+use crate::ai::moves::{roll_battle, Move, MoveType};
+use crate::ai::possible_move::{possible_moves, SearchBudget};
+use crate::app_state::GameMode;
+use crate::model::profile::Profile;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Battles fought in simulation to estimate each attack
+const BATTLE_TRIALS: usize = 64;
+
+// In case the dice keep going our way
+const MAXIMUM_ATTACKS: usize = 64;
+
+// A territory to take this turn, enemy cities in the order they can be reached
+#[derive(Debug)]
+pub struct Objective {
+    pub territory: String,
+    pub targets: Vec<usize>,
+}
+
+// What a city adds to its owner's score, ignoring the armies in it
+fn city_worth(world_state: &WorldState, world_fixed: &WorldFixed, city_index: usize, profile: &Profile) -> f32 {
+    let city = &world_state.cities[city_index];
+    (city.size as f32 + city.growth) * profile.city_size_multiplier
+        + world_fixed.cities[city_index].role.score(profile)
+        + city.fortification as f32 * profile.fortification_value
+}
+
+// Taking this city would leave the whole territory to us
+fn completes_territory(world_state: &WorldState, world_fixed: &WorldFixed, player_index: usize, target: usize) -> bool {
+    let territory = world_fixed.territories.get(&world_fixed.cities[target].territory_name).unwrap();
+    territory
+        .cities
+        .iter()
+        .all(|city_index| *city_index == target || world_state.cities[*city_index].owner == Some(player_index))
+}
+
+// Chance of taking the city and the average armies each side loses
+fn battle_odds(attackers: usize, defenders: usize, defence_bonus: u8) -> (f32, f32, f32) {
+    let mut rng = StdRng::seed_from_u64((attackers as u64) << 32 | (defenders as u64) << 8 | defence_bonus as u64);
+    let mut wins = 0;
+    let mut attacker_losses = 0;
+    let mut defender_losses = 0;
+    for _ in 0..BATTLE_TRIALS {
+        let (source_losses, target_losses) = roll_battle(&mut rng, attackers, defenders, defence_bonus);
+        if target_losses >= defenders {
+            wins += 1;
+        }
+        attacker_losses += source_losses;
+        defender_losses += target_losses;
+    }
+    let trials = BATTLE_TRIALS as f32;
+    (wins as f32 / trials, attacker_losses as f32 / trials, defender_losses as f32 / trials)
+}
+
+// Expected change in the current player's score, None if the attack can't be made, including from a city that
+// isn't theirs
+pub fn attack_value(world_state: &WorldState, world_fixed: &WorldFixed, the_move: &Move) -> Option<f32> {
+    let source = the_move.city_source?;
+    let target = the_move.city_target?;
+    let player_index = world_state.get_current_player_index();
+    let profile = &world_state.get_player_for_index(player_index).profile;
+    if world_state.cities[source].owner != Some(player_index) || !world_state.can_attack(source, target) {
+        return None;
+    }
+    let attackers = match the_move.move_type {
+        MoveType::AttackCity if world_state.cities[source].armies >= profile.minimum_armies => {
+            world_state.cities[source].armies - 1
+        }
+        MoveType::CavalryAttack if world_state.cities[source].cavalry > 0 => world_state.cities[source].cavalry,
+        _ => return None,
+    };
+    let defenders = world_state.cities[target].armies;
+    let defence_bonus = world_state.cities[target].defence_bonus(&world_fixed.cities[target]);
+    let (win, attacker_losses, defender_losses) = battle_odds(attackers, defenders, defence_bonus);
+
+    // Armies count for more on a front line, so weigh the exchange as if they all were
+    let mut gain = city_worth(world_state, world_fixed, target, profile);
+    if completes_territory(world_state, world_fixed, player_index, target) {
+        gain += profile.territory_value;
+    }
+    let army_worth = profile.army_multiplier + profile.army_bordering;
+    Some(win * gain + (defender_losses - attacker_losses) * army_worth)
+}
+
+// Territories reachable from cities we already hold, rated by what there is to gain for the armies in the way
+pub fn rate_objectives(
+    world_state: &WorldState,
+    world_fixed: &WorldFixed,
+    player_index: usize,
+) -> Vec<(f32, Objective)> {
+    let profile = &world_state.get_player_for_index(player_index).profile;
+    let mut objectives = Vec::new();
+    for territory in world_fixed.territories.values() {
+        let others =
+            territory.cities.iter().filter(|city_index| world_state.cities[**city_index].owner != Some(player_index));
+
+        // Cities of treaty partners can't be attacked, and keep the territory from ever being whole
+        let enemies: Vec<usize> = others
+            .clone()
+            .filter(|city_index| match world_state.cities[**city_index].owner {
+                Some(owner) => !world_state.diplomacy.at_peace(player_index, owner),
+                None => true,
+            })
+            .copied()
+            .collect();
+
+        // Work inwards from the cities on our border
+        let mut queue: VecDeque<usize> = enemies
+            .iter()
+            .filter(|city_index| {
+                world_fixed.cities[**city_index]
+                    .connections
+                    .iter()
+                    .any(|connection| world_state.cities[connection.city2].owner == Some(player_index))
+            })
+            .copied()
+            .collect();
+        let mut targets = Vec::new();
+        while let Some(city_index) = queue.pop_front() {
+            if targets.contains(&city_index) {
+                continue;
+            }
+            targets.push(city_index);
+            for connection in world_fixed.cities[city_index].connections.iter() {
+                if enemies.contains(&connection.city2) && !targets.contains(&connection.city2) {
+                    queue.push_back(connection.city2);
+                }
+            }
+        }
+        if targets.is_empty() {
+            continue;
+        }
+
+        let cost: f32 = targets
+            .iter()
+            .map(|city_index| {
+                let city = &world_state.cities[*city_index];
+                (city.armies + 1) as f32 + city.defence_bonus(&world_fixed.cities[*city_index]) as f32
+            })
+            .sum();
+        let mut value: f32 =
+            targets.iter().map(|city_index| city_worth(world_state, world_fixed, *city_index, profile)).sum();
+        if targets.len() == others.count() {
+            value += profile.territory_value;
+        }
+        objectives.push((value / cost, Objective { territory: territory.name.clone(), targets }));
+    }
+    objectives
+}
+
+// Our cities next to a target, strongest first
+fn staging_cities(
+    world_state: &WorldState,
+    world_fixed: &WorldFixed,
+    player_index: usize,
+    target: usize,
+) -> Vec<usize> {
+    let mut cities: Vec<usize> = world_fixed.cities[target]
+        .connections
+        .iter()
+        .map(|connection| connection.city2)
+        .filter(|city_index| world_state.cities[*city_index].owner == Some(player_index))
+        .collect();
+    cities.sort_by_key(|city_index| std::cmp::Reverse(world_state.cities[*city_index].armies));
+    cities
+}
+
+// Pile the new armies into the city leading the attack, spilling over along the route and then the rest of the front
+fn place_armies(world_state: &mut WorldState, world_fixed: &WorldFixed, objective: &Objective) -> Vec<Move> {
+    let player_index = world_state.get_current_player_index();
+    let mut priority = Vec::new();
+    for target in objective.targets.iter() {
+        for city_index in staging_cities(world_state, world_fixed, player_index, *target) {
+            if !priority.contains(&city_index) {
+                priority.push(city_index);
+            }
+        }
+    }
+    for city_index in 0..world_state.cities.len() {
+        if world_state.cities[city_index].owner == Some(player_index)
+            && world_state.borders_enemy(world_fixed, city_index)
+            && !priority.contains(&city_index)
+        {
+            priority.push(city_index);
+        }
+    }
+
+    let mut plan = Vec::new();
+    while world_state.get_current_player().armies_to_assign > 0 {
+        let Some(city_index) =
+            priority.iter().find(|city_index| world_state.cities[**city_index].armies < MAXIMUM_ARMIES_PER_CITY)
+        else {
+            break;
+        };
+        let the_move = Move::new_place_army(*city_index);
+        the_move.do_move(world_state, world_fixed);
+        plan.push(the_move);
+    }
+    plan
+}
+
+// A deliberate slip, more likely the easier the computer is set
+fn mistake(world_state: &mut WorldState) -> bool {
+    let chance = world_state.get_current_player().profile.mistake_chance;
+    chance > 0.0 && world_state.rng.random::<f32>() < chance
+}
+
+// Whether to go ahead with an attack, the value is kept on the move so playback can tell if it still holds
+fn decide(world_state: &mut WorldState, world_fixed: &WorldFixed, the_move: &mut Move) -> Option<bool> {
+    let value = attack_value(world_state, world_fixed, the_move)?;
+    the_move.score_portion = if value > 0.0 { value.ceil() } else { value.floor() } as i32;
+    Some((value > 0.0) != mistake(world_state))
+}
+
+// Work along the route, each attack from the strongest neighbour, giving up when it stops paying
+fn attack_objective(
+    world_state: &mut WorldState,
+    world_fixed: &WorldFixed,
+    objective: &Objective,
+    nodes: &AtomicUsize,
+) -> Vec<Move> {
+    let player_index = world_state.get_current_player_index();
+    let mut plan = Vec::new();
+    for target in objective.targets.iter() {
+        while world_state.cities[*target].owner != Some(player_index) && plan.len() < MAXIMUM_ATTACKS {
+            let Some(source) = staging_cities(world_state, world_fixed, player_index, *target).first().copied() else {
+                return plan;
+            };
+            let mut the_move = Move::new_attack_city(source, *target);
+            nodes.fetch_add(1, Ordering::Relaxed);
+            if decide(world_state, world_fixed, &mut the_move) != Some(true) {
+                return plan;
+            }
+            the_move.do_move(world_state, world_fixed);
+            plan.push(the_move);
+        }
+    }
+    plan
+}
+
+// Anything else still worth a go, searched once and then each re-checked against the world as it now is, in the
+// order the search rates them, until nothing is
+fn attack_opportunities(
+    world_state: &mut WorldState,
+    world_fixed: &WorldFixed,
+    nodes: &Arc<AtomicUsize>,
+    plan: &mut Vec<Move>,
+) {
+    let budget = SearchBudget::new(world_state.get_current_player().profile.thinking_time, nodes.clone());
+    let mut candidates = possible_moves(world_state, world_fixed, GameMode::Game, &budget);
+    for _ in 0..MAXIMUM_ATTACKS {
+        let mut chosen = None;
+        for the_move in candidates.iter_mut() {
+            nodes.fetch_add(1, Ordering::Relaxed);
+            if decide(world_state, world_fixed, the_move) == Some(true) {
+                chosen = Some(the_move.clone());
+                break;
+            }
+        }
+        let Some(the_move) = chosen else {
+            break;
+        };
+        the_move.do_move(world_state, world_fixed);
+        plan.push(the_move);
+    }
+}
+
+// The whole turn built around one objective, played out on the given copy of the world
+pub fn plan_objective(
+    world_state: &mut WorldState,
+    world_fixed: &WorldFixed,
+    nodes: &Arc<AtomicUsize>,
+) -> Option<Vec<Move>> {
+    let player_index = world_state.get_current_player_index();
+    let mut objectives = rate_objectives(world_state, world_fixed, player_index);
+    if objectives.is_empty() {
+        return None;
+    }
+
+    // Best value for the armies, unless it slips up and goes for any old one
+    let chosen = if mistake(world_state) {
+        world_state.rng.random_range(0..objectives.len())
+    } else {
+        (0..objectives.len()).max_by(|a, b| objectives[*a].0.total_cmp(&objectives[*b].0)).unwrap()
+    };
+    let (_, objective) = objectives.swap_remove(chosen);
+    debug_log!("Objective: {} via {:?}", objective.territory, objective.targets);
+
+    let mut plan = place_armies(world_state, world_fixed, &objective);
+    plan.extend(attack_objective(world_state, world_fixed, &objective, nodes));
+    attack_opportunities(world_state, world_fixed, nodes, &mut plan);
+    Some(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::moves::MoveResult;
+    use crate::model::test_world::line_world;
+
+    // Planned as if the first capture goes our way, then played out where it can't. Beyond it the cities are
+    // nobody's, so the enemy city could otherwise be made to attack them for us
+    #[test]
+    fn attacks_from_a_city_not_taken_after_all_are_refused() {
+        let (mut world_state, world_fixed) = line_world();
+        world_state.cities[3].armies = MAXIMUM_ARMIES_PER_CITY;
+        for city in world_state.cities[4..].iter_mut() {
+            city.armies = 1;
+        }
+        for city in world_state.cities[5..].iter_mut() {
+            city.owner = None;
+        }
+        world_state.update_scores(&world_fixed);
+        let mut planning = world_state.clone();
+        let plan = plan_objective(&mut planning, &world_fixed, &Arc::new(AtomicUsize::new(0))).unwrap();
+        assert!(plan.iter().any(|the_move| the_move.is_attack() && the_move.city_source == Some(4)));
+
+        // One attacker against a full garrison can't win
+        world_state.cities[3].armies = 2;
+        world_state.cities[4].armies = MAXIMUM_ARMIES_PER_CITY;
+        for the_move in plan.iter().filter(|the_move| the_move.is_attack()) {
+            let ours = world_state.cities[the_move.city_source.unwrap()].owner == Some(0);
+            if !ours {
+                assert_eq!(attack_value(&world_state, &world_fixed, the_move), None);
+                assert_eq!(the_move.do_move(&mut world_state, &world_fixed), MoveResult::Refused);
+            } else {
+                the_move.do_move(&mut world_state, &world_fixed);
+            }
+        }
+        assert_eq!(world_state.cities[4].owner, Some(1));
+        assert!(world_state.cities[5..].iter().all(|city| city.owner.is_none()));
+        assert!(!world_state.players[0].captured_this_turn);
+    }
+}
@@ -1,9 +1,44 @@
 use crate::ai::army_placement::ap_build_list_of_possibles;
 use crate::ai::game::game_build_list_of_possibles;
 use crate::ai::moves::{Move, MoveType};
+use crate::ai::transposition::{rehash, zobrist, TranspositionTable};
 use crate::app_state::GameMode;
 use crate::model::world_fixed::WorldFixed;
 use crate::model::world_state::WorldState;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Only the most promising moves are followed further down
+const SEARCH_BREADTH: usize = 8;
+
+// Shared by every thread working on one search
+pub struct SearchBudget {
+    deadline: Instant,
+    nodes: Arc<AtomicUsize>,
+    hits: AtomicUsize,
+}
+
+impl SearchBudget {
+    // Nodes are counted into a shared total so progress can be watched from another thread
+    pub fn new(thinking_time: Duration, nodes: Arc<AtomicUsize>) -> Self {
+        Self { deadline: Instant::now() + thinking_time, nodes, hits: AtomicUsize::new(0) }
+    }
+
+    pub fn expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.nodes.load(Ordering::Relaxed)
+    }
+
+    // Positions found in a table rather than searched again
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
 
 fn reduce_down_to_limited_list(data_in: Vec<Move>) -> Vec<Move> {
     let mut results = data_in;
@@ -11,112 +46,187 @@ fn reduce_down_to_limited_list(data_in: Vec<Move>) -> Vec<Move> {
     results
 }
 
-fn go_deeper<F>(
+// What stays the same all the way down one search, including the phase, which never moves on to the next
+struct Search<'a> {
+    world_fixed: &'a WorldFixed,
+    mode: GameMode,
+    budget: &'a SearchBudget,
+}
+
+// Rate a move by the best that can follow it
+fn deepen(
     world_state: &WorldState,
-    world_fixed: &WorldFixed,
+    search: &Search,
+    result: &mut Move,
+    depth: usize,
+    hash: u64,
+    table: &mut TranspositionTable,
+) {
+    let world_fixed = search.world_fixed;
+    let remaining = world_state.get_current_player().profile.search_depth - (depth + 1);
+    let mut child = world_state.clone();
+    result.do_move(&mut child, world_fixed);
+    child.update_scores_after(world_fixed, &result.touched_cities());
+
+    // Been here already by another route?
+    let child_hash = rehash(hash, world_state, &child, &result.touched_cities());
+    if let Some(score) = table.probe(child_hash, remaining) {
+        result.score_portion = result.score_portion.max(score);
+        return;
+    }
+
+    // Further placements only go in this city or later ones, other orders end up in the same place
+    let first_city = match (result.move_type, &search.mode) {
+        (MoveType::PlaceArmy, GameMode::ArmyPlacement) => result.city_source.unwrap(),
+        _ => 0,
+    };
+
+    // Recurse
+    result.child_moves = search_moves(&child, search, depth + 1, child_hash, first_city, Some(&mut *table));
+    if let Some(best) = result.child_moves.first() {
+        table.store(child_hash, remaining, best.score_portion);
+        result.score_portion = result.score_portion.max(best.score_portion);
+    }
+}
+
+// Rate the best few moves by what they lead to. Each from the top is searched on its own thread with its own
+// table, and everything below it in order, so no thread can change what another finds
+fn go_deeper(
+    world_state: &WorldState,
+    search: &Search,
     data_in: Vec<Move>,
-    mut f: F,
     depth: usize,
-) -> Vec<Move>
-where
-    F: FnMut(&WorldState, usize) -> Vec<Move>,
-{
+    hash: u64,
+    table: Option<&mut TranspositionTable>,
+) -> Vec<Move> {
     let mut results = data_in;
-    let desired_depth = world_state.current_player.as_ref().unwrap().borrow().profile.search_depth;
-    if depth != desired_depth {
-        for result in &mut results {
-            let mut world_state = result.world_state.deep_clone();
-
-            // Update player
-            {
-                let player_index = world_state.current_player.as_ref().unwrap().borrow().index;
-                let new_player = &mut world_state.players[player_index];
-
-                // Phase done
-                match &world_state.mode {
-                    GameMode::ArmyPlacement => {
-                        new_player.borrow_mut().armies_to_assign -= 1;
-                        if new_player.borrow().armies_to_assign == 0 {
-                            world_state.mode = GameMode::Game;
-                        }
-                    }
-                    GameMode::Game => {}
-                    _ => panic!("Unknown mode"),
-                }
-            }
+    let desired_depth = world_state.get_current_player().profile.search_depth;
+    if depth + 1 >= desired_depth || search.budget.expired() {
+        return results;
+    }
 
-            // Next player and pretend again
-            /*                    loop {
-                let mut other_player_state = new_state.clone();
-                if move_to_next_player(&mut other_player_state, app_state, player_index) {
-                    break;
-                }
-            }*/
-
-            // Recurse
-            let mut additional_moves = f(&world_state, depth + 1);
-            result.child_moves.append(&mut additional_moves);
-        }
+    let breadth = results.len().min(SEARCH_BREADTH);
+    match table {
+        None => results[..breadth].par_iter_mut().for_each(|result| {
+            let mut table = TranspositionTable::default();
+            deepen(world_state, search, result, depth, hash, &mut table);
+            search.budget.hits.fetch_add(table.hits(), Ordering::Relaxed);
+        }),
+        Some(table) => results[..breadth].iter_mut().for_each(|result| {
+            deepen(world_state, search, result, depth, hash, table);
+        }),
     }
-    results
+    reduce_down_to_limited_list(results)
 }
 
+// Candidates are scored in parallel, each on its own copy of the world with the same dice, so the
+// outcome only depends on the seed unless the time budget runs out part way through
 pub fn possible_moves(
     world_state: &WorldState,
-    world_fixed: &mut WorldFixed,
-    depth: usize,
+    world_fixed: &WorldFixed,
     mode: GameMode,
+    budget: &SearchBudget,
+) -> Vec<Move> {
+    // Full scoring and hashing once at the root, below that each move only redoes what it touched
+    let mut root = world_state.clone();
+    root.update_scores(world_fixed);
+    let hash = zobrist(&root);
+    search_moves(&root, &Search { world_fixed, mode, budget }, 0, hash, 0, None)
+}
+
+fn search_moves(
+    world_state: &WorldState,
+    search: &Search,
+    depth: usize,
+    hash: u64,
+    first_city: usize,
+    table: Option<&mut TranspositionTable>,
 ) -> Vec<Move> {
+    let world_fixed = search.world_fixed;
     let mut results: Vec<Move> = Vec::new();
 
     // Build a list of all possible moves
-    let world_state = world_state.deep_clone();
     let current_player = world_state.get_current_player();
-    match mode {
+    match search.mode {
         GameMode::Randomising => panic!("This should not happen"),
         GameMode::ArmyPlacement => {
-            if current_player.borrow().armies_to_assign == 0 {
+            if current_player.armies_to_assign == 0 {
                 return results;
             }
-            results = ap_build_list_of_possibles(&world_state, current_player.borrow().index);
+            results = ap_build_list_of_possibles(world_state, current_player.index, first_city);
         }
-        GameMode::Game => results = game_build_list_of_possibles(&world_state, current_player.borrow().index),
+        GameMode::Game => results = game_build_list_of_possibles(world_state, world_fixed, current_player.index),
         _ => {}
     }
 
-    // Now do each of the moves and work out the scores
-    for result in &mut results {
-        let mut world_state = world_state.deep_clone();
-        let current_player = world_state.get_current_player();
+    // Now do each of the moves on a copy and work out the scores
+    results.par_iter_mut().for_each(|result| {
+        let mut world_state = world_state.clone();
 
         // If this is an attack, work out combat delta
-        let attack_delta = if result.move_type == MoveType::AttackCity {
-            let source_armies = world_state.cities[result.city_source.unwrap()].borrow().armies as f32;
-            let target_armies = world_state.cities[result.city_target.unwrap()].borrow().armies as f32;
-            source_armies - target_armies
-        } else {
-            0f32
+        let attack_delta = match result.move_type {
+            MoveType::AttackCity => {
+                let source_armies = world_state.cities[result.city_source.unwrap()].armies as f32;
+                let target_armies = world_state.cities[result.city_target.unwrap()].armies as f32;
+                source_armies - target_armies
+            }
+            MoveType::CavalryAttack => {
+                let cavalry = world_state.cities[result.city_source.unwrap()].cavalry as f32;
+                let target_armies = world_state.cities[result.city_target.unwrap()].armies as f32;
+                cavalry - target_armies
+            }
+            _ => 0f32,
         };
 
-        result.do_move(&mut world_state);
-        world_state.update_scores(world_fixed);
-        let all_scores: i32 = world_state.players.iter().map(|p| p.borrow().score).sum();
-        let mut current_player_score = world_state.current_player.as_ref().unwrap().borrow().score;
+        result.do_move(&mut world_state, world_fixed);
+        world_state.update_scores_after(world_fixed, &result.touched_cities());
+        let all_scores: i32 = world_state.players.iter().map(|p| p.score).sum();
+        let mut current_player_score = world_state.get_current_player().score;
 
         // If this is an attack, encourage it
-        if result.move_type == MoveType::AttackCity {
-            current_player_score += (attack_delta * current_player.borrow().profile.attack_delta_multiplier) as i32;
+        if result.move_type == MoveType::AttackCity || result.move_type == MoveType::CavalryAttack {
+            current_player_score += (attack_delta * current_player.profile.attack_delta_multiplier) as i32;
         }
 
         result.score_portion = (current_player_score * 10000) / all_scores;
-        result.world_state = world_state;
-    }
+        search.budget.nodes.fetch_add(1, Ordering::Relaxed);
+    });
 
     // Select x of the list
     results = reduce_down_to_limited_list(results);
 
     // Go deeper if required
-    //    results = go_deeper(&world_state, results, possible_moves, depth);
+    go_deeper(world_state, search, results, depth, hash, table)
+}
 
-    results
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_world::line_world;
+
+    // The same world and seed searched twice, tables and all, with time enough to never be cut short
+    #[test]
+    fn searches_from_a_seed_come_out_the_same() {
+        let (mut world_state, world_fixed) = line_world();
+        world_state.players[0].armies_to_assign = 3;
+        world_state.players[0].profile.search_depth = 4;
+
+        // A second front, so attacks made in either order can meet in the tables
+        world_state.cities[6].owner = Some(0);
+        world_state.cities[6].armies = 8;
+        world_state.cities[3].armies = 8;
+        for mode in [GameMode::ArmyPlacement, GameMode::Game] {
+            let search = || {
+                let budget = SearchBudget::new(Duration::from_secs(600), Arc::new(AtomicUsize::new(0)));
+                let moves = possible_moves(&world_state, &world_fixed, mode.clone(), &budget);
+                (format!("{:?}", moves), budget.nodes(), budget.hits())
+            };
+            let (moves, nodes, hits) = search();
+            assert_eq!((moves, nodes, hits), search());
+
+            // Placements are only ever made in city order, so it's the attacks that find their way back
+            assert!(nodes > 0);
+            assert!(mode == GameMode::ArmyPlacement || hits > 0);
+        }
+    }
 }
@@ -0,0 +1,80 @@
+use crate::ai::development::best_upgrade;
+use crate::ai::game::two_hop_targets;
+use crate::ai::moves::Move;
+use crate::model::economy::{CAVALRY_COST, FORTIFY_COST, MAXIMUM_CAVALRY_PER_CITY, REGIMENT_COST};
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+
+// How many more armies the strongest neighbouring enemy has
+fn threat(world_state: &WorldState, world_fixed: &WorldFixed, city_index: usize) -> i32 {
+    let city = &world_state.cities[city_index];
+    let mut threat = 0;
+    for connection in world_fixed.cities[city_index].connections.iter() {
+        let other_city = &world_state.cities[connection.city2];
+        if other_city.owner != city.owner {
+            threat = threat.max(other_city.armies as i32 - city.armies as i32);
+        }
+    }
+    threat
+}
+
+// Walls for the city most outnumbered by a neighbour
+fn best_fortify(world_state: &WorldState, world_fixed: &WorldFixed, current_player: usize) -> Option<Move> {
+    let mut best: Option<(usize, i32)> = None;
+    for (city_index, city) in world_state.cities.iter().enumerate() {
+        if city.owner != Some(current_player) || !city.can_fortify() {
+            continue;
+        }
+        let threat = threat(world_state, world_fixed, city_index);
+        if threat > 0 && (best.is_none() || threat > best.unwrap().1) {
+            best = Some((city_index, threat));
+        }
+    }
+    best.map(|(city_index, _)| Move::new_fortify_city(city_index))
+}
+
+// Cavalry go to the best garrisoned city with something to ride at
+fn best_cavalry(world_state: &WorldState, world_fixed: &WorldFixed, current_player: usize) -> Option<Move> {
+    let mut best: Option<(usize, usize)> = None;
+    for (city_index, city) in world_state.cities.iter().enumerate() {
+        if city.owner != Some(current_player)
+            || city.cavalry >= MAXIMUM_CAVALRY_PER_CITY
+            || two_hop_targets(world_state, world_fixed, city_index).is_empty()
+        {
+            continue;
+        }
+        if best.is_none() || city.armies > best.unwrap().1 {
+            best = Some((city_index, city.armies));
+        }
+    }
+    best.map(|(city_index, _)| Move::new_buy_cavalry(city_index))
+}
+
+// Made on what the player can see, and for real
+fn buy(world_state: &mut WorldState, view: &mut WorldState, world_fixed: &WorldFixed, the_move: Option<Move>) {
+    if let Some(the_move) = the_move {
+        the_move.do_move(view, world_fixed);
+        the_move.do_move(world_state, world_fixed);
+    }
+}
+
+// Chosen on what the player can see, so nothing hidden sways what's bought
+pub fn ai_purchase(world_state: &mut WorldState, world_fixed: &WorldFixed) {
+    let current_player = world_state.get_current_player_index();
+    let mut view = world_state.as_seen_by(world_fixed, current_player);
+
+    // Defence first, then development and a raiding party, anything left buys regiments
+    if view.get_current_player().gold >= FORTIFY_COST {
+        let fortify = best_fortify(&view, world_fixed, current_player);
+        buy(world_state, &mut view, world_fixed, fortify);
+    }
+    let upgrade = best_upgrade(&view, world_fixed);
+    buy(world_state, &mut view, world_fixed, upgrade);
+    if view.get_current_player().gold >= CAVALRY_COST {
+        let cavalry = best_cavalry(&view, world_fixed, current_player);
+        buy(world_state, &mut view, world_fixed, cavalry);
+    }
+    while view.get_current_player().gold >= REGIMENT_COST {
+        buy(world_state, &mut view, world_fixed, Some(Move::new_buy_regiment()));
+    }
+}
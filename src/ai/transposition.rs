@@ -0,0 +1,80 @@
+use crate::model::city::CityState;
+use crate::model::world_state::WorldState;
+use std::collections::HashMap;
+
+const OWNER: u64 = 1;
+const ARMIES: u64 = 2;
+const PHASE: u64 = 3;
+const PLAYER: u64 = 4;
+
+// Fixed random looking key for each feature and value, mixed as splitmix64 does, so there's no key table to size
+fn key(feature: u64, index: usize, value: usize) -> u64 {
+    let mut z = (feature << 56 ^ (index as u64) << 24 ^ value as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+fn city_key(city_index: usize, city: &CityState) -> u64 {
+    key(OWNER, city_index, city.owner.map_or(0, |owner| owner + 1)) ^ key(ARMIES, city_index, city.armies)
+}
+
+fn turn_key(world_state: &WorldState) -> u64 {
+    key(PHASE, 0, world_state.mode.clone() as usize) ^ key(PLAYER, 0, world_state.current_player)
+}
+
+// Zobrist hash of who holds each city with how many armies, whose go it is and the phase
+pub fn zobrist(world_state: &WorldState) -> u64 {
+    let mut hash = turn_key(world_state);
+    for (city_index, city) in world_state.cities.iter().enumerate() {
+        hash ^= city_key(city_index, city);
+    }
+    hash
+}
+
+// Hash after a move, from the hash before it and the cities it touched
+pub fn rehash(hash: u64, before: &WorldState, after: &WorldState, touched: &[usize]) -> u64 {
+    let mut hash = hash ^ turn_key(before) ^ turn_key(after);
+    for &city_index in touched {
+        hash ^= city_key(city_index, &before.cities[city_index]) ^ city_key(city_index, &after.cities[city_index]);
+    }
+    debug_assert_eq!(hash, zobrist(after), "Incremental hash differs from a full rehash");
+    hash
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    remaining: usize,
+    score: i32,
+}
+
+// Best score found below each position, one for each subtree from the top so it's only ever filled and read
+// in the same order
+#[derive(Default)]
+pub struct TranspositionTable {
+    entries: HashMap<u64, Entry>,
+    hits: usize,
+}
+
+impl TranspositionTable {
+    // Only trusted if it was searched at least as deep as we'd go now
+    pub fn probe(&mut self, hash: u64, remaining: usize) -> Option<i32> {
+        let entry = *self.entries.get(&hash)?;
+        if entry.remaining < remaining {
+            return None;
+        }
+        self.hits += 1;
+        Some(entry.score)
+    }
+
+    pub fn store(&mut self, hash: u64, remaining: usize, score: i32) {
+        let entry = self.entries.entry(hash).or_insert(Entry { remaining, score });
+        if remaining >= entry.remaining {
+            *entry = Entry { remaining, score };
+        }
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
@@ -0,0 +1,39 @@
+use crate::ai::computer_turn::plan_turn;
+use crate::ai::moves::Move;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+use std::sync::atomic::AtomicUsize;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::thread;
+
+pub struct AiRequest {
+    pub world_state: WorldState,
+    pub nodes: Arc<AtomicUsize>,
+    pub reply: Sender<Vec<Move>>,
+}
+
+// Background thread doing the AI's thinking, so the render loop keeps going
+pub struct AiWorker {
+    requests: Sender<AiRequest>,
+}
+
+impl AiWorker {
+    pub fn spawn(world_fixed: Arc<WorldFixed>) -> Self {
+        let (requests, inbox) = channel::<AiRequest>();
+        thread::Builder::new()
+            .name("ai".to_string())
+            .spawn(move || {
+                for request in inbox {
+                    let plan = plan_turn(request.world_state, &world_fixed, &request.nodes);
+                    let _ = request.reply.send(plan);
+                }
+            })
+            .expect("Can't start AI thread");
+        Self { requests }
+    }
+
+    pub fn request(&self, request: AiRequest) {
+        self.requests.send(request).expect("AI worker has stopped");
+    }
+}
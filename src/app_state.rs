@@ -1,27 +1,35 @@
-use crate::model::city::CityRR;
-use crate::model::player::Player;
-use crate::model::profile::Profile;
+use crate::ai::computer_turn::AiTurn;
+use crate::camera::{Camera, HOME};
+use crate::lib::cbor;
+use crate::model::city::CityRole;
+use crate::model::history::History;
+use crate::model::player::{Player, PlayerStatic};
+use crate::model::profile::{Difficulty, Profile};
+use crate::model::setup::{GameSetup, FACTION_NAMES, PLAYER_COLOURS};
 use crate::model::world_fixed::WorldFixed;
 use crate::model::world_state::WorldState;
-use rand::prelude::SliceRandom;
-use rand::rng;
+use crate::render::map_style::{MapTheme, RegionTints};
 use sdl2::video::Window;
 use skia_safe::svg::Dom;
-use skia_safe::{Color, FontMgr, Path, Point, Size};
-use std::cell::RefCell;
-use std::rc::Rc;
+use skia_safe::{Data, FontMgr, Image, Path, Point, Size};
+use std::sync::Arc;
 use std::time::Instant;
 
 const SVG_CORNER: &str = include_str!("../assets/Corner.svg");
 const SVG_SIDE: &str = include_str!("../assets/Side.svg");
 const SVG_BUTTON: &str = include_str!("../assets/Button.svg");
+const PNG_ACROPOLIS: &[u8] = include_bytes!("../assets/Acropolis.png");
+const PNG_AGROPOLIS: &[u8] = include_bytes!("../assets/Agropolis.png");
+const PNG_METROPOLIS: &[u8] = include_bytes!("../assets/Metropolis.png");
 pub const NOISE_MIX: f32 = 0.075;
 pub const MIN_ZOOM: f32 = 4.2;
 
 #[derive(PartialEq, Clone, Default, Debug)]
 pub enum GameMode {
     #[default]
+    Setup,
     Randomising,
+    Draft,
     ArmyPlacement,
     Game,
     End,
@@ -33,48 +41,91 @@ pub struct GFXState {
     pub half_width: i32,
     pub half_height: i32,
     pub dpi: f32,
+    // The drawable's own size in pixels, as it doesn't always divide evenly by the DPI
+    pub drawable_width: i32,
+    pub drawable_height: i32,
+}
+
+impl GFXState {
+    // Sizes are in window units, the drawable underneath can have more pixels on a high DPI display
+    pub fn new(window: &Window) -> Self {
+        let (width, height) = window.size();
+        let (drawable_width, drawable_height) = window.drawable_size();
+        let dpi = if width == 0 { 1.0 } else { drawable_width as f32 / width as f32 };
+        GFXState {
+            width: width as i32,
+            height: height as i32,
+            half_width: width as i32 / 2,
+            half_height: height as i32 / 2,
+            dpi,
+            drawable_width: drawable_width as i32,
+            drawable_height: drawable_height as i32,
+        }
+    }
+
+    pub fn pixel_size(&self) -> (i32, i32) {
+        (self.drawable_width, self.drawable_height)
+    }
 }
 
 pub struct Resource {
     pub side_path: Dom,
     pub corner_path: Dom,
     pub button_path: Dom,
+    pub acropolis_image: Image,
+    pub agropolis_image: Image,
+    pub metropolis_image: Image,
+}
+
+impl Resource {
+    pub fn role_image(&self, role: CityRole) -> Option<&Image> {
+        match role {
+            CityRole::Town => None,
+            CityRole::Acropolis => Some(&self.acropolis_image),
+            CityRole::Agropolis => Some(&self.agropolis_image),
+            CityRole::Metropolis => Some(&self.metropolis_image),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct CitySelection {
     pub last_selection: Instant,
-    pub last_city_hover: Option<CityRR>,
-    pub last_city_selection: Option<CityRR>,
-    pub last_army_city_selection: Option<CityRR>,
-    pub minimum_allowed_distance: f32,
+    pub last_city_hover: Option<usize>,
+    pub last_city_selection: Option<usize>,
+    pub last_army_city_selection: Option<usize>,
+    pub attack_target: Option<usize>,
     pub assign_speed: u128,
 }
 
 pub struct AppState {
     pub world_state: WorldState,
-    pub world_fixed: WorldFixed,
-    pub num_of_players: usize,
+    pub world_fixed: Arc<WorldFixed>,
+    pub setup: GameSetup,
     pub gfx: GFXState,
     pub res: Resource,
     pub show_labels: bool,
     pub show_shadows: bool,
+    pub show_diplomacy: bool,
+    pub map_theme: MapTheme,
+    pub region_tints: RegionTints,
     pub fps: f64,
     pub phase: f32,
     pub selection: CitySelection,
     pub hover: Point,
     pub target: Point,
     pub panning: bool,
+    pub minimap_dragging: bool,
     pub zoom: f32,
+    pub camera: Camera,
+    pub ai_turn: AiTurn,
+    pub history: History,
 }
 
 impl AppState {
-    pub fn new(window: &Window, dpi: f32, mut world_state: WorldState) -> Self {
-        let width = window.size().0 as i32;
-        let height = window.size().1 as i32;
-        println!("Screen resolution: {}x{}", width, height);
-        let half_width = width / 2;
-        let half_height = height / 2;
+    pub fn new(window: &Window, mut world_state: WorldState) -> Self {
+        let gfx = GFXState::new(window);
+        println!("Screen resolution: {}x{}, scaling factor {}", gfx.width, gfx.height, gfx.dpi);
 
         let st = "";
         Path::from_svg(st);
@@ -84,87 +135,17 @@ impl AppState {
         side_path.set_container_size(Size::new(40.0, 200.0));
         let button_path = Dom::from_str(SVG_BUTTON, FontMgr::new()).expect("Error loading SVG");
 
-        let gfx = GFXState {
-            width,
-            height,
-            half_width,
-            half_height,
-            dpi,
-        };
-
         let res = Resource {
             corner_path,
             side_path,
             button_path,
+            acropolis_image: Image::from_encoded(Data::new_copy(PNG_ACROPOLIS)).expect("Error loading PNG"),
+            agropolis_image: Image::from_encoded(Data::new_copy(PNG_AGROPOLIS)).expect("Error loading PNG"),
+            metropolis_image: Image::from_encoded(Data::new_copy(PNG_METROPOLIS)).expect("Error loading PNG"),
         };
 
-        let num_of_players = 5;
-
-        let mut possible_names = vec![
-            "The Britannian Dominion",
-            "The Red Tsardom",
-            "The Iron Kaisers",
-            "The Rising Shogunate",
-            "The Gaulish Syndicate",
-            "The Yankee Federation",
-            "The Ottoman Remnants",
-            "The Austro Imperium",
-            "The Persian Ascendants",
-            "The Italian Legions",
-            "The Dragon Empire",
-            "The Iberian Dominion",
-            "The Nordic Coalition",
-            "The Balkan Confederacy",
-            "The Egyptian Dynasts",
-            "The Prussian Order",
-            "The Celtic Union",
-            "The Maharaja Confederation",
-            "The Andean Empire",
-            "The Hellenic Guardians",
-        ];
-        let mut rng = rng(); // Create a random number generator
-        possible_names.shuffle(&mut rng);
-
-        // Player colours
-        let player_colours = [
-            vec![Color::from_rgb(128, 128, 255), Color::BLACK],
-            vec![Color::from_rgb(255, 128, 128), Color::BLACK],
-            vec![Color::from_rgb(128, 255, 128), Color::BLACK],
-            vec![Color::from_rgb(255, 255, 128), Color::BLACK],
-            vec![Color::from_rgb(128, 255, 255), Color::BLACK],
-        ];
-
-        let profile = Profile {
-            human: false,
-            search_depth: 3,
-            city_size_multiplier: 5.0,
-            army_multiplier: 1.0,
-            army_same_territory: 10.0,
-            army_bordering: 25.0,
-            minimum_armies: 3,
-            attack_delta_multiplier: 50.0,
-        };
-
-        let mut ai_profile = profile.clone();
-        ai_profile.human = false;
-
-        // Create player(s)
-        for i in 0..num_of_players {
-            let player = Player {
-                index: i,
-                name: possible_names[i].parse().unwrap(),
-                colours: player_colours[i].clone(),
-                armies_to_assign: 10,
-                armies_to_assign_fractional: 0.0,
-                score: 0,
-                profile: if i == 0 {
-                    profile.clone()
-                } else {
-                    ai_profile.clone()
-                },
-            };
-            world_state.players.push(Rc::new(RefCell::new(player)));
-        }
+        // Players and the world are made once the game has been set up
+        world_state.mode = GameMode::Setup;
 
         AppState {
             world_state,
@@ -173,30 +154,90 @@ impl AppState {
                 last_city_selection: None,
                 last_city_hover: None,
                 last_army_city_selection: None,
-                minimum_allowed_distance: 18.0, //12.0,
+                attack_target: None,
                 assign_speed: 0,
             },
             hover: Default::default(),
-            target: Point::new(25.0, -9.5),
+            target: HOME,
             panning: false,
-            world_fixed: WorldFixed::default(),
+            minimap_dragging: false,
+            world_fixed: Arc::new(WorldFixed::default()),
+            setup: GameSetup::new(5, Difficulty::default()),
             gfx,
             res,
-            num_of_players,
             fps: 0.0,
             show_labels: true,
             show_shadows: true,
+            show_diplomacy: false,
+            map_theme: MapTheme::default(),
+            region_tints: RegionTints::default(),
             phase: 0.0,
             zoom: MIN_ZOOM,
+            camera: Camera::new(HOME, MIN_ZOOM),
+            ai_turn: AiTurn::Idle,
+            history: History::default(),
         }
     }
 
-    pub fn reset(&mut self) {
-        self.zoom = MIN_ZOOM;
-        self.target = Point::new(25.0, -9.5);
+    // Players as set up, a seeded world built to the chosen size, then on to handing out or drafting the cities
+    pub fn start_game(&mut self) {
+        let mut world_state = WorldState::default();
+        world_state.reseed(self.setup.seed);
+        let mut world_fixed = WorldFixed::default();
+        create_players(&mut world_state, &mut world_fixed, &self.setup);
+
+        // Optional rules
+        world_state.fog_of_war = self.setup.fog_of_war;
+
+        self.world_state = world_state;
+        self.world_fixed = Arc::new(world_fixed);
+        cbor::import(self);
+        self.camera.bounds = self.world_fixed.map_bounds();
+        self.region_tints = RegionTints::default();
+
+        // Some or all of the cities may be left for the players to pick
+        let cities = self.world_state.cities_to_assign.len();
+        self.world_state.cities_to_draft = self.setup.cities_to_draft(cities);
+        self.world_state.mode = if self.world_state.cities_to_draft == cities {
+            GameMode::Draft
+        } else {
+            GameMode::Randomising
+        };
     }
 
     pub fn show_all_info(&self) -> bool {
         self.show_labels
     }
+
+    // Whoever is at the screen, the first human while the computer plays, or nobody if all are computers
+    pub fn viewing_player(&self) -> Option<usize> {
+        if self.world_state.get_current_player().is_human() {
+            return Some(self.world_state.current_player);
+        }
+        self.world_state.players.iter().find(|player| player.is_human()).map(|player| player.index)
+    }
+}
+
+// Names, colours and profiles for the players as set up, each computer player with its own personality
+pub fn create_players(world_state: &mut WorldState, world_fixed: &mut WorldFixed, setup: &GameSetup) {
+    for (i, player_setup) in setup.players.iter().enumerate() {
+        world_fixed.players.push(PlayerStatic {
+            name: FACTION_NAMES[player_setup.name].to_string(),
+            colours: PLAYER_COLOURS[player_setup.colour].to_vec(),
+        });
+        let player = Player {
+            index: i,
+            armies_to_assign: setup.starting_armies,
+            armies_to_assign_fractional: 0.0,
+            score: 0,
+            profile: Profile {
+                human: player_setup.human,
+                ..Profile::new(player_setup.personality, player_setup.difficulty)
+            },
+            cards: Vec::new(),
+            captured_this_turn: false,
+            gold: 0,
+        };
+        world_state.players.push(player);
+    }
 }
@@ -0,0 +1,200 @@
+use crate::app_state::{AppState, GameMode, MIN_ZOOM};
+use skia_safe::{Contains, Point, Rect, Vector};
+use std::time::{Duration, Instant};
+
+pub const MAX_ZOOM: f32 = 15.0;
+pub const HOME: Point = Point::new(25.0, -9.5);
+
+// How quickly the view closes on where it's headed, per second
+const SMOOTHING: f32 = 12.0;
+
+// Speed left over from a drag dies away at this rate per second
+const FRICTION: f32 = 5.0;
+
+// A drag that stopped this long before letting go doesn't carry on
+const FLING_WINDOW: Duration = Duration::from_millis(80);
+
+// Pixels from the window's edge that scroll the map, and how fast in pixels a second
+const EDGE: f32 = 12.0;
+const EDGE_SPEED: f32 = 700.0;
+
+// Share of the view around the edge, where something the computer does is worth flying to
+const FOLLOW_MARGIN: f32 = 0.2;
+
+// Where the view is going, what's shown in `AppState::target` and `zoom` follows it a little each frame
+#[derive(Debug)]
+pub struct Camera {
+    pub goal_target: Point,
+    pub goal_zoom: f32,
+    pub bounds: Rect,
+    pub mouse_inside: bool,
+    // Screen point that stays over the same place while zooming, the middle if none
+    anchor: Option<Point>,
+    // In map units a second, from a drag
+    velocity: Vector,
+    last_drag: Instant,
+    last_update: Instant,
+}
+
+impl Camera {
+    pub fn new(target: Point, zoom: f32) -> Self {
+        Self {
+            goal_target: target,
+            goal_zoom: zoom,
+            bounds: Rect::new_empty(),
+            // No Enter event comes for a window that opens under the mouse
+            mouse_inside: true,
+            anchor: None,
+            velocity: Vector::default(),
+            last_drag: Instant::now(),
+            last_update: Instant::now(),
+        }
+    }
+
+    // The middle of the view stays over the map
+    fn clamp(&self, point: Point) -> Point {
+        if self.bounds.is_empty() {
+            return point;
+        }
+        Point::new(
+            point.x.clamp(self.bounds.left, self.bounds.right),
+            point.y.clamp(self.bounds.top, self.bounds.bottom),
+        )
+    }
+}
+
+// From window to map coordinates, as the view is now
+pub fn screen_to_world(app_state: &AppState, point: Point) -> Point {
+    let half = Vector::new(app_state.gfx.half_width as f32, app_state.gfx.half_height as f32);
+    (point - half) * (1.0 / app_state.zoom) + app_state.target
+}
+
+pub fn world_to_screen(app_state: &AppState, point: Point) -> Point {
+    let half = Vector::new(app_state.gfx.half_width as f32, app_state.gfx.half_height as f32);
+    (point - app_state.target) * app_state.zoom + half
+}
+
+// The part of the map filling the window
+pub fn view_rect(app_state: &AppState) -> Rect {
+    let top_left = screen_to_world(app_state, Point::new(0.0, 0.0));
+    let bottom_right = screen_to_world(app_state, Point::new(app_state.gfx.width as f32, app_state.gfx.height as f32));
+    Rect::new(top_left.x, top_left.y, bottom_right.x, bottom_right.y)
+}
+
+// Zoom by steps, keeping what's under `anchor` there
+pub fn zoom_at(app_state: &mut AppState, delta: f32, anchor: Option<Point>) {
+    let camera = &mut app_state.camera;
+    camera.goal_zoom = (camera.goal_zoom + delta).clamp(MIN_ZOOM, MAX_ZOOM);
+    camera.anchor = anchor;
+}
+
+// By screen pixels, so a step looks the same at any zoom
+pub fn pan(app_state: &mut AppState, x: f32, y: f32) {
+    let camera = &mut app_state.camera;
+    camera.goal_target = camera.clamp(camera.goal_target + Vector::new(x, y) * (1.0 / camera.goal_zoom));
+    camera.velocity = Vector::default();
+}
+
+// The map follows the mouse exactly while dragging, and keeps some of its speed when let go
+pub fn drag(app_state: &mut AppState, x_rel: f32, y_rel: f32) {
+    let delta = Vector::new(-x_rel, -y_rel) * (1.0 / app_state.zoom);
+    let camera = &mut app_state.camera;
+    let elapsed = camera.last_drag.elapsed().as_secs_f32().max(0.001);
+    camera.velocity = camera.velocity * 0.5 + delta * (0.5 / elapsed);
+    camera.last_drag = Instant::now();
+    camera.goal_target = camera.clamp(camera.goal_target + delta);
+    app_state.target = camera.clamp(app_state.target + delta);
+}
+
+pub fn release(app_state: &mut AppState) {
+    let camera = &mut app_state.camera;
+    if camera.last_drag.elapsed() > FLING_WINDOW {
+        camera.velocity = Vector::default();
+    }
+}
+
+pub fn fly_to(app_state: &mut AppState, point: Point) {
+    let camera = &mut app_state.camera;
+    camera.goal_target = camera.clamp(point);
+    camera.velocity = Vector::default();
+}
+
+// Fly to where the computer is acting unless it's already in plain view
+pub fn follow(app_state: &mut AppState, point: Point) {
+    let screen = world_to_screen(app_state, point);
+    let (width, height) = (app_state.gfx.width as f32, app_state.gfx.height as f32);
+    let (margin_x, margin_y) = (width * FOLLOW_MARGIN, height * FOLLOW_MARGIN);
+    let inner = Rect::new(margin_x, margin_y, width - margin_x, height - margin_y);
+    if !inner.contains(screen) {
+        fly_to(app_state, point);
+    }
+}
+
+pub fn reset(app_state: &mut AppState) {
+    let camera = &mut app_state.camera;
+    camera.goal_target = HOME;
+    camera.goal_zoom = MIN_ZOOM;
+    camera.anchor = None;
+    camera.velocity = Vector::default();
+}
+
+// Once a frame, scroll at the edges, coast after a drag and ease towards the goal
+pub fn update_camera(app_state: &mut AppState) {
+    let dt = app_state.camera.last_update.elapsed().as_secs_f32().min(0.1);
+    app_state.camera.last_update = Instant::now();
+    if app_state.world_state.mode == GameMode::Setup {
+        return;
+    }
+
+    // Edge scrolling, only while the mouse is in the window
+    if app_state.camera.mouse_inside && !app_state.panning {
+        let hover = app_state.hover;
+        let step = EDGE_SPEED * dt;
+        let x = if hover.x < EDGE {
+            -step
+        } else if hover.x > app_state.gfx.width as f32 - EDGE {
+            step
+        } else {
+            0.0
+        };
+        let y = if hover.y < EDGE {
+            -step
+        } else if hover.y > app_state.gfx.height as f32 - EDGE {
+            step
+        } else {
+            0.0
+        };
+        if x != 0.0 || y != 0.0 {
+            pan(app_state, x, y);
+        }
+    }
+
+    let blend = 1.0 - (-SMOOTHING * dt).exp();
+    let camera = &mut app_state.camera;
+
+    // Inertia
+    if !app_state.panning && camera.velocity.length() > 0.01 {
+        camera.goal_target = camera.clamp(camera.goal_target + camera.velocity * dt);
+        camera.velocity = camera.velocity * (-FRICTION * dt).exp();
+    }
+
+    // Zoom, moving the goal too so the anchor's spot stays put
+    let before = app_state.zoom;
+    app_state.zoom += (camera.goal_zoom - app_state.zoom) * blend;
+    if (camera.goal_zoom - app_state.zoom).abs() < 0.001 {
+        app_state.zoom = camera.goal_zoom;
+    }
+    if let Some(anchor) = camera.anchor {
+        let offset = anchor - Vector::new(app_state.gfx.half_width as f32, app_state.gfx.half_height as f32);
+        let shift = offset * (1.0 / before - 1.0 / app_state.zoom);
+        camera.goal_target = camera.clamp(camera.goal_target + shift);
+        app_state.target += shift;
+    }
+    if app_state.zoom == camera.goal_zoom {
+        camera.anchor = None;
+    }
+
+    // Pan
+    app_state.target += (camera.goal_target - app_state.target) * blend;
+    app_state.target = camera.clamp(app_state.target);
+}
@@ -1,103 +1,465 @@
+use crate::ai::diplomacy::ai_consider;
+use crate::ai::game::two_hop_targets;
+use crate::ai::moves::Move;
 use crate::app_state::{AppState, GameMode};
+use crate::camera::{drag, fly_to, pan, release, reset, screen_to_world, zoom_at};
+use crate::keymap::{Action, Gamepad};
+use crate::lib::skia::button_rect;
+use crate::model::card::find_set;
 use crate::model::city::SIZE;
+use crate::model::diplomacy::DiplomacyAction;
+use crate::model::economy::Purchase;
+use crate::model::history::Command;
+use crate::model::world_state::WorldState;
 use crate::next_turn;
+use crate::render::card_hand::trade_button_position;
+use crate::render::diplomacy::diplomacy_at;
+use crate::render::minimap::minimap_to_world;
+use crate::render::purchase::purchase_at;
+use crate::render::randomising::draft;
+use crate::render::setup::{setup_field_at, start_button_position};
+use crate::render::turn_controls::{confirm_button_position, undo_button_position};
 use sdl2::mouse::{MouseButton, MouseWheelDirection};
-use skia_safe::Point;
+use skia_safe::{Contains, Point};
 
 const THRESHOLD: i32 = 64;
+const ZOOM_STEP: f32 = 0.25;
+const PAN_STEP: f32 = 40.0;
+const PAD_PAN_SPEED: f32 = 12.0;
+const PAD_ZOOM_SPEED: f32 = 0.05;
 
+// Towards or away from wherever the mouse is pointing
 pub fn handle_mouse_wheel(app_state: &mut AppState, _direction: MouseWheelDirection, precise_y: f32) {
-    let delta = precise_y * 0.25;
-    app_state.zoom += delta;
-    app_state.zoom = app_state.zoom.clamp(crate::app_state::MIN_ZOOM, 15.0);
+    zoom_at(app_state, precise_y * ZOOM_STEP, Some(app_state.hover));
+}
+
+// Any city under a point on the screen, whoever has it
+fn city_at(app_state: &AppState, point: Point) -> Option<usize> {
+    let mp = screen_to_world(app_state, point);
+    let radius = SIZE * app_state.zoom / app_state.gfx.dpi / 2.0;
+    app_state.world_fixed.index.cities_near(&app_state.world_fixed, mp, radius).first().copied()
 }
 
 pub fn handle_mouse_motion(app_state: &mut AppState, x: i32, y: i32, x_rel: i32, y_rel: i32) {
-    let world_state = &app_state.world_state;
-    let world_fixed = &mut app_state.world_fixed;
     app_state.hover = Point::new(x as f32, y as f32);
+    if app_state.world_state.mode == GameMode::Setup {
+        return;
+    }
+    if app_state.minimap_dragging {
+        if let Some(point) = minimap_to_world(app_state, app_state.hover) {
+            fly_to(app_state, point);
+        }
+        return;
+    }
+    let world_state = &app_state.world_state;
+    let world_fixed = &app_state.world_fixed;
     if app_state.panning {
         // Calculate mouse movement delta
         if x_rel.abs() < THRESHOLD && y_rel.abs() < THRESHOLD {
             // Update camera target based on mouse movement
-            app_state.target.x -= x_rel as f32 / app_state.zoom;
-            app_state.target.y -= y_rel as f32 / app_state.zoom;
+            drag(app_state, x_rel as f32, y_rel as f32);
         }
     } else {
         // Mouse over?
-        if world_state.current_player.is_none() {
-            return;
-        }
-        let current_player = &world_state.get_current_player();
-        let mut mp = Point::new(x as f32, y as f32);
-
-        // Do reverse matrix transform
-        mp.x -= app_state.gfx.half_width as f32;
-        mp.y -= app_state.gfx.half_height as f32;
-        mp.x /= app_state.zoom;
-        mp.y /= app_state.zoom;
-        mp.x += app_state.target.x;
-        mp.y += app_state.target.y;
-
-        if world_state.mode == GameMode::ArmyPlacement {
+        let current_player = world_state.get_current_player_index();
+        let mp = screen_to_world(app_state, app_state.hover);
+
+        let placing = placing_armies(world_state);
+        if placing {
             app_state.selection.last_city_selection = None;
         } else {
             app_state.selection.last_city_hover = None;
         }
-        for city in &world_state.cities {
-            if city.borrow().owner.is_some() {
-                let owner = city.borrow().owner.unwrap();
-                let delta = city.borrow().statics.borrow().location.p - mp;
-                let diff = (delta.x * delta.x + delta.y * delta.y).sqrt();
-                if diff <= SIZE * app_state.zoom / app_state.gfx.dpi / 2.0 && owner == current_player.borrow().index {
-                    if world_state.mode == GameMode::ArmyPlacement {
-                        app_state.selection.last_city_selection = Some(city.clone());
-                    } else {
-                        app_state.selection.last_city_hover = Some(city.clone());
-                    }
-                }
+        // The nearest city under the mouse that can be picked, while drafting it's those nobody has yet
+        let radius = SIZE * app_state.zoom / app_state.gfx.dpi / 2.0;
+        let picked = world_fixed.index.cities_near(world_fixed, mp, radius).into_iter().find(|city_index| {
+            let owner = world_state.cities[*city_index].owner;
+            match world_state.mode {
+                GameMode::Draft => owner.is_none(),
+                _ => owner == Some(current_player),
+            }
+        });
+        if let Some(city_index) = picked {
+            if placing {
+                app_state.selection.last_city_selection = Some(city_index);
+            } else {
+                app_state.selection.last_city_hover = Some(city_index);
             }
         }
     }
 }
 
-pub fn handle_mouse_button_down(app_state: &mut AppState, button: MouseButton) {
-    let player = app_state.world_state.current_player.as_ref();
-    let is_human = if app_state.world_state.current_player.is_none() {
-        false
-    } else {
-        app_state.world_state.current_player.as_ref().unwrap().borrow().is_human()
-    };
+pub fn handle_mouse_button_down(app_state: &mut AppState, button: MouseButton, clicks: u8) {
+    // Options change where they're clicked, until the game starts
+    if app_state.world_state.mode == GameMode::Setup {
+        if button != MouseButton::Left {
+            return;
+        }
+        if button_rect(start_button_position(&app_state.gfx)).contains(app_state.hover) {
+            app_state.start_game();
+        } else if let Some(field) = setup_field_at(&app_state.gfx, &app_state.setup, app_state.hover) {
+            app_state.setup.step(field);
+        }
+        return;
+    }
+    let is_human = app_state.world_state.get_current_player().is_human();
     if button == MouseButton::Right {
         app_state.panning = true;
     } else if button == MouseButton::Left {
+        // Click or drag on the minimap to look somewhere else
+        if let Some(point) = minimap_to_world(app_state, app_state.hover) {
+            app_state.minimap_dragging = true;
+            fly_to(app_state, point);
+            return;
+        }
+
+        // Double-click to centre on a city, unless it's one of ours having armies placed on it click by click
+        if clicks == 2 {
+            if let Some(city_index) = city_at(app_state, app_state.hover) {
+                let world_state = &app_state.world_state;
+                let placing = placing_armies(world_state)
+                    && world_state.cities[city_index].owner == Some(world_state.get_current_player_index());
+                if !placing {
+                    fly_to(app_state, app_state.world_fixed.cities[city_index].location.p);
+                }
+            }
+        }
+
+        // Take back or confirm what's been done this go
+        if humans_go(app_state) {
+            let hover = app_state.hover;
+            if app_state.history.can_undo() && button_rect(undo_button_position(&app_state.gfx)).contains(hover) {
+                undo(app_state);
+                return;
+            }
+            if can_confirm(&app_state.world_state)
+                && button_rect(confirm_button_position(&app_state.gfx)).contains(hover)
+            {
+                confirm(app_state);
+                return;
+            }
+        }
+
         match app_state.world_state.mode {
-            GameMode::ArmyPlacement => {
-                if is_human && app_state.selection.last_city_selection.is_some() {
-                    let city = app_state.selection.last_city_selection.clone();
-                    let owner = app_state.world_state.get_player_for_index(city.as_ref().unwrap()
-                        .borrow().owner.unwrap());
-                    if owner.borrow().is_human() {
-                        city.unwrap().borrow_mut().armies += 1;
-                        player.unwrap().borrow_mut().armies_to_assign -= 1;
-                        if player.unwrap().borrow().armies_to_assign == 0 {
-                            app_state.selection.last_city_hover = app_state.selection.last_city_selection.clone();
-                            app_state.selection.last_city_selection = None;
+            GameMode::ArmyPlacement | GameMode::Draft if is_human => select(app_state),
+            GameMode::Game => {
+                // Offers and answers in the diplomacy panel
+                if is_human && app_state.show_diplomacy {
+                    if let Some((other, action)) = diplomacy_at(app_state, app_state.hover) {
+                        handle_diplomacy(app_state, other, action);
+                    }
+                    return;
+                }
+
+                // Trade in cards?
+                if is_human && app_state.selection.last_city_selection.is_none() {
+                    let set = find_set(&app_state.world_state.get_current_player().cards);
+                    if let Some(set) = set {
+                        if button_rect(trade_button_position(&app_state.gfx)).contains(app_state.hover) {
+                            let command = Command::TradeCards(set);
+                            app_state.history.apply(&mut app_state.world_state, &app_state.world_fixed, command);
+                            return;
                         }
                     }
-                    next_turn(app_state);
                 }
-            }
-            GameMode::Game => {
-                app_state.selection.last_city_selection = app_state.selection.last_city_hover.clone();
+
+                // Reinforcements, a trade-in or bought regiments to place first
+                if is_human && placing_armies(&app_state.world_state) {
+                    select(app_state);
+                    return;
+                }
+
+                // Spend gold on the selected city?
+                if let (true, Some(city_index)) = (is_human, app_state.selection.last_city_selection) {
+                    if let Some(purchase) = purchase_at(&app_state.gfx, app_state.hover) {
+                        let purchase_move = match purchase {
+                            Purchase::Regiment => Move::new_buy_regiment(),
+                            Purchase::Upgrade => Move::new_upgrade_city(city_index),
+                            Purchase::Fortify => Move::new_fortify_city(city_index),
+                            Purchase::Cavalry => Move::new_buy_cavalry(city_index),
+                        };
+                        let command = Command::Move(purchase_move);
+                        app_state.history.apply(&mut app_state.world_state, &app_state.world_fixed, command);
+                        return;
+                    }
+                }
+                app_state.selection.last_city_selection = app_state.selection.last_city_hover;
+                app_state.selection.attack_target = None;
             }
             _ => {}
         }
     }
 }
 
+// Armies going down a click at a time, all through the opening placement and during a human's go whenever
+// reinforcements, a trade-in or bought regiments are waiting
+pub fn placing_armies(world_state: &WorldState) -> bool {
+    match world_state.mode {
+        GameMode::ArmyPlacement => true,
+        GameMode::Game => world_state.get_current_player().is_human() && world_state.can_place_army(),
+        _ => false,
+    }
+}
+
+// Phases where a human's actions are kept until they confirm them
+pub fn has_turn_controls(mode: &GameMode) -> bool {
+    matches!(mode, GameMode::ArmyPlacement | GameMode::Game)
+}
+
+// Only on a human's go, never into the computer's
+fn humans_go(app_state: &AppState) -> bool {
+    let world_state = &app_state.world_state;
+    has_turn_controls(&world_state.mode) && world_state.get_current_player().is_human()
+}
+
+pub fn undo(app_state: &mut AppState) {
+    if humans_go(app_state) && app_state.history.undo(&mut app_state.world_state) {
+        app_state.selection.last_city_selection = None;
+        app_state.selection.attack_target = None;
+    }
+}
+
+pub fn redo(app_state: &mut AppState) {
+    if humans_go(app_state) {
+        app_state.history.redo(&mut app_state.world_state, &app_state.world_fixed);
+    }
+}
+
+// Done for this go, nothing before now can be taken back
+// Regiments have to be placed before the go can end
+pub fn can_confirm(world_state: &WorldState) -> bool {
+    !(world_state.mode == GameMode::ArmyPlacement && world_state.can_place_army())
+}
+
+pub fn confirm(app_state: &mut AppState) {
+    if !humans_go(app_state) || !can_confirm(&app_state.world_state) {
+        return;
+    }
+    app_state.history.clear();
+    app_state.selection.last_city_selection = None;
+    app_state.selection.attack_target = None;
+    next_turn(app_state);
+}
+
+// The city the keys act on, picked for placing armies or spending on, or hovered while drafting
+fn focused_city(app_state: &AppState) -> Option<usize> {
+    match app_state.world_state.mode {
+        GameMode::Draft => app_state.selection.last_city_hover,
+        _ => app_state.selection.last_city_selection,
+    }
+}
+
+fn focus_city(app_state: &mut AppState, city_index: usize) {
+    match app_state.world_state.mode {
+        GameMode::Draft => app_state.selection.last_city_hover = Some(city_index),
+        _ => app_state.selection.last_city_selection = Some(city_index),
+    }
+    app_state.selection.attack_target = None;
+    fly_to(app_state, app_state.world_fixed.cities[city_index].location.p);
+}
+
+// One step on or back through the cities the human can act on, in the map's order
+fn cycle_city(app_state: &mut AppState, forward: bool) {
+    let world_state = &app_state.world_state;
+    if !world_state.get_current_player().is_human() {
+        return;
+    }
+    let current_player = world_state.get_current_player_index();
+    let cities: Vec<usize> = (0..world_state.cities.len())
+        .filter(|city_index| match world_state.mode {
+            GameMode::Draft => world_state.cities[*city_index].owner.is_none(),
+            GameMode::ArmyPlacement | GameMode::Game => world_state.cities[*city_index].owner == Some(current_player),
+            _ => false,
+        })
+        .collect();
+    if cities.is_empty() {
+        return;
+    }
+    let position = focused_city(app_state).and_then(|focused| cities.iter().position(|city| *city == focused));
+    let next = match (position, forward) {
+        (None, true) => 0,
+        (None, false) => cities.len() - 1,
+        (Some(position), true) => (position + 1) % cities.len(),
+        (Some(position), false) => (position + cities.len() - 1) % cities.len(),
+    };
+    focus_city(app_state, cities[next]);
+}
+
+// On to the next city the selected one is connected to and could attack, or could raid through another city
+// if it has cavalry
+fn cycle_target(app_state: &mut AppState) {
+    let world_state = &app_state.world_state;
+    if world_state.mode != GameMode::Game || !world_state.get_current_player().is_human() {
+        return;
+    }
+    let Some(source) = app_state.selection.last_city_selection else {
+        return;
+    };
+    let targets: Vec<usize> = if world_state.cities[source].cavalry > 0 {
+        two_hop_targets(world_state, &app_state.world_fixed, source)
+    } else {
+        app_state.world_fixed.cities[source]
+            .connections
+            .iter()
+            .map(|connection| connection.city2)
+            .filter(|target| world_state.can_attack(source, *target))
+            .collect()
+    };
+    if targets.is_empty() {
+        app_state.selection.attack_target = None;
+        return;
+    }
+    let position = app_state.selection.attack_target.and_then(|target| targets.iter().position(|city| *city == target));
+    let next = targets[position.map_or(0, |position| (position + 1) % targets.len())];
+    app_state.selection.attack_target = Some(next);
+    fly_to(app_state, app_state.world_fixed.cities[next].location.p);
+}
+
+// Place an army on, draft, or attack from the focused city, whichever the phase calls for
+fn select(app_state: &mut AppState) {
+    if !app_state.world_state.get_current_player().is_human() {
+        return;
+    }
+    match app_state.world_state.mode {
+        _ if placing_armies(&app_state.world_state) => {
+            let Some(city_index) = app_state.selection.last_city_selection else {
+                return;
+            };
+            let world_state = &mut app_state.world_state;
+            if world_state.cities[city_index].owner == Some(world_state.get_current_player_index())
+                && world_state.can_place_army()
+            {
+                let command = Command::Move(Move::new_place_army(city_index));
+                app_state.history.apply(world_state, &app_state.world_fixed, command);
+                if !app_state.world_state.can_place_army() {
+                    app_state.selection.last_city_hover = app_state.selection.last_city_selection;
+                    app_state.selection.last_city_selection = None;
+                }
+            }
+        }
+        GameMode::Draft => {
+            let Some(city_index) = app_state.selection.last_city_hover else {
+                return;
+            };
+            if app_state.world_state.cities[city_index].owner.is_none() {
+                draft(app_state, city_index);
+                app_state.selection.last_city_hover = None;
+                next_turn(app_state);
+            }
+        }
+        GameMode::Game => {
+            let (Some(source), Some(target)) =
+                (app_state.selection.last_city_selection, app_state.selection.attack_target)
+            else {
+                return;
+            };
+
+            // Further afield only cavalry can reach
+            if !app_state.world_fixed.cities[source].connections.iter().any(|connection| connection.city2 == target) {
+                return;
+            }
+            let command = Command::Move(Move::new_attack_city(source, target));
+            app_state.history.apply(&mut app_state.world_state, &app_state.world_fixed, command);
+
+            // Taken, or too weak to go again
+            let world_state = &app_state.world_state;
+            let minimum_armies = world_state.get_current_player().profile.minimum_armies;
+            if !world_state.can_attack(source, target) || world_state.cities[source].armies < minimum_armies {
+                app_state.selection.attack_target = None;
+            }
+        }
+        _ => {}
+    }
+}
+
+// Send the selected city's cavalry against the target, the garrison stays at home
+fn raid(app_state: &mut AppState) {
+    let world_state = &app_state.world_state;
+    if world_state.mode != GameMode::Game || !world_state.get_current_player().is_human() {
+        return;
+    }
+    let (Some(source), Some(target)) = (app_state.selection.last_city_selection, app_state.selection.attack_target)
+    else {
+        return;
+    };
+    let command = Command::Move(Move::new_cavalry_attack(source, target));
+    app_state.history.apply(&mut app_state.world_state, &app_state.world_fixed, command);
+
+    // Taken, or no riders left to go again
+    let world_state = &app_state.world_state;
+    if !world_state.can_attack(source, target) || world_state.cities[source].cavalry == 0 {
+        app_state.selection.attack_target = None;
+    }
+}
+
+// What a key or controller button was bound to in the keymap, quitting and the window are left to the main loop
+pub fn handle_action(app_state: &mut AppState, action: Action) {
+    match action {
+        Action::ShowLabels => app_state.show_labels = true,
+        Action::HideLabels => app_state.show_labels = false,
+        Action::Quit | Action::ToggleFullscreen => {}
+        _ if app_state.world_state.mode == GameMode::Setup => {}
+        Action::ResetView => reset(app_state),
+        Action::PanLeft => pan(app_state, -PAN_STEP, 0.0),
+        Action::PanRight => pan(app_state, PAN_STEP, 0.0),
+        Action::PanUp => pan(app_state, 0.0, -PAN_STEP),
+        Action::PanDown => pan(app_state, 0.0, PAN_STEP),
+        Action::ZoomIn => zoom_at(app_state, ZOOM_STEP, None),
+        Action::ZoomOut => zoom_at(app_state, -ZOOM_STEP, None),
+        Action::ToggleDiplomacy => app_state.show_diplomacy = !app_state.show_diplomacy,
+        Action::CycleMapTheme => {
+            app_state.map_theme = app_state.map_theme.next();
+            debug_log!("Map theme {}", app_state.map_theme.name());
+        }
+        Action::NextCity => cycle_city(app_state, true),
+        Action::PreviousCity => cycle_city(app_state, false),
+        Action::NextTarget => cycle_target(app_state),
+        Action::Select => select(app_state),
+        Action::Raid => raid(app_state),
+        Action::EndPhase => confirm(app_state),
+        Action::Undo => undo(app_state),
+        Action::Redo => redo(app_state),
+    }
+}
+
+// Held sticks and triggers move the camera a little every frame
+pub fn handle_gamepad(app_state: &mut AppState, gamepad: &Gamepad) {
+    if app_state.world_state.mode == GameMode::Setup {
+        return;
+    }
+    if gamepad.pan_x != 0.0 || gamepad.pan_y != 0.0 {
+        pan(app_state, gamepad.pan_x * PAD_PAN_SPEED, gamepad.pan_y * PAD_PAN_SPEED);
+    }
+    if gamepad.zoom_in != gamepad.zoom_out {
+        zoom_at(app_state, (gamepad.zoom_in - gamepad.zoom_out) * PAD_ZOOM_SPEED, None);
+    }
+}
+
+// A computer player answers an offer there and then, a human sees it in their panel on their go. Treaties
+// can't be taken back, so neither can anything done before them
+fn handle_diplomacy(app_state: &mut AppState, other: usize, action: DiplomacyAction) {
+    app_state.history.clear();
+    let world_state = &mut app_state.world_state;
+    let player_index = world_state.get_current_player_index();
+    match action {
+        DiplomacyAction::Propose(kind, turns) => {
+            world_state.diplomacy.propose(player_index, other, kind, turns);
+            if !world_state.players[other].is_human() {
+                let proposal = *world_state.diplomacy.proposal(player_index, other).unwrap();
+                let accept = ai_consider(world_state, &app_state.world_fixed, &proposal);
+                world_state.diplomacy.answer(player_index, other, accept);
+            }
+        }
+        DiplomacyAction::Accept => world_state.diplomacy.answer(other, player_index, true),
+        DiplomacyAction::Decline => world_state.diplomacy.answer(other, player_index, false),
+    }
+}
+
 pub fn handle_mouse_button_up(app_state: &mut AppState, button: MouseButton) {
     if button == MouseButton::Right {
         app_state.panning = false;
+        release(app_state);
+    } else if button == MouseButton::Left {
+        app_state.minimap_dragging = false;
     }
 }
@@ -0,0 +1,170 @@
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::{Keycode, Mod};
+use std::collections::HashMap;
+
+const DEFAULT_KEYMAP: &str = include_str!("../assets/keymap.txt");
+const KEYMAP_FILE: &str = "keymap.txt";
+
+// Stick and trigger travel under this is ignored, out of i16::MAX
+const DEADZONE: f32 = 8000.0;
+
+// Everything a key or button can do, the mouse does the same through input.rs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    NextCity,
+    PreviousCity,
+    NextTarget,
+    Select,
+    Raid,
+    EndPhase,
+    Undo,
+    Redo,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    ShowLabels,
+    HideLabels,
+    ResetView,
+    ToggleDiplomacy,
+    ToggleFullscreen,
+    CycleMapTheme,
+    Quit,
+}
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "next_city" => Some(Action::NextCity),
+            "previous_city" => Some(Action::PreviousCity),
+            "next_target" => Some(Action::NextTarget),
+            "select" => Some(Action::Select),
+            "raid" => Some(Action::Raid),
+            "end_phase" => Some(Action::EndPhase),
+            "undo" => Some(Action::Undo),
+            "redo" => Some(Action::Redo),
+            "pan_left" => Some(Action::PanLeft),
+            "pan_right" => Some(Action::PanRight),
+            "pan_up" => Some(Action::PanUp),
+            "pan_down" => Some(Action::PanDown),
+            "zoom_in" => Some(Action::ZoomIn),
+            "zoom_out" => Some(Action::ZoomOut),
+            "show_labels" => Some(Action::ShowLabels),
+            "hide_labels" => Some(Action::HideLabels),
+            "reset_view" => Some(Action::ResetView),
+            "toggle_diplomacy" => Some(Action::ToggleDiplomacy),
+            "toggle_fullscreen" => Some(Action::ToggleFullscreen),
+            "cycle_map_theme" => Some(Action::CycleMapTheme),
+            "quit" => Some(Action::Quit),
+            _ => None,
+        }
+    }
+}
+
+// A key, and whether it's pressed with control held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct KeyBinding {
+    key: Keycode,
+    ctrl: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Keymap {
+    keys: HashMap<KeyBinding, Action>,
+    buttons: HashMap<Button, Action>,
+}
+
+impl Keymap {
+    // The built in bindings, then any the player has changed from a file
+    pub fn load(path: Option<&str>) -> Self {
+        let mut keymap = Keymap::default();
+        keymap.parse(DEFAULT_KEYMAP, "default keymap");
+        let path = path.unwrap_or(KEYMAP_FILE);
+        match std::fs::read_to_string(path) {
+            Ok(text) => {
+                println!("Using keymap {}", path);
+                keymap.parse(&text, path);
+            }
+            Err(e) if path != KEYMAP_FILE => eprintln!("Could not read keymap {}: {}", path, e),
+            Err(_) => {}
+        }
+        keymap
+    }
+
+    // A line replaces whatever the action was bound to before, so a file need only list what it changes
+    fn parse(&mut self, text: &str, source: &str) {
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some((name, bindings)) = line.split_once('=') else {
+                eprintln!("{}:{}: expected `action = binding, ...`", source, number + 1);
+                continue;
+            };
+            let Some(action) = Action::from_name(name.trim()) else {
+                eprintln!("{}:{}: unknown action {}", source, number + 1, name.trim());
+                continue;
+            };
+            self.keys.retain(|_, bound| *bound != action);
+            self.buttons.retain(|_, bound| *bound != action);
+            for binding in bindings.split(',').map(str::trim).filter(|binding| !binding.is_empty()) {
+                if !self.bind(binding, action) {
+                    eprintln!("{}:{}: unknown key or button {}", source, number + 1, binding);
+                }
+            }
+        }
+    }
+
+    fn bind(&mut self, binding: &str, action: Action) -> bool {
+        if let Some(name) = binding.strip_prefix("Pad+") {
+            let Some(button) = Button::from_string(name) else {
+                return false;
+            };
+            self.buttons.insert(button, action);
+            return true;
+        }
+        let (name, ctrl) = match binding.strip_prefix("Ctrl+") {
+            Some(name) => (name, true),
+            None => (binding, false),
+        };
+        let Some(key) = Keycode::from_name(name) else {
+            return false;
+        };
+        self.keys.insert(KeyBinding { key, ctrl }, action);
+        true
+    }
+
+    pub fn key_action(&self, key: Keycode, keymod: Mod) -> Option<Action> {
+        let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+        self.keys.get(&KeyBinding { key, ctrl }).copied()
+    }
+
+    pub fn button_action(&self, button: Button) -> Option<Action> {
+        self.buttons.get(&button).copied()
+    }
+}
+
+// Where the sticks and triggers are held, the left stick pans and the triggers zoom every frame
+#[derive(Debug, Default)]
+pub struct Gamepad {
+    pub pan_x: f32,
+    pub pan_y: f32,
+    pub zoom_in: f32,
+    pub zoom_out: f32,
+}
+
+impl Gamepad {
+    pub fn axis_motion(&mut self, axis: Axis, value: i16) {
+        let amount = if (value as f32).abs() < DEADZONE { 0.0 } else { value as f32 / i16::MAX as f32 };
+        match axis {
+            Axis::LeftX => self.pan_x = amount,
+            Axis::LeftY => self.pan_y = amount,
+            Axis::TriggerRight => self.zoom_in = amount,
+            Axis::TriggerLeft => self.zoom_out = amount,
+            _ => {}
+        }
+    }
+}
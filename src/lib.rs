@@ -0,0 +1,164 @@
+// Running commentary on the dice and the computer's thinking, only when started with --debug. Defined ahead of
+// the modules so they can all use it
+#[macro_export]
+macro_rules! debug_log {
+    ($($arg:tt)*) => {
+        if $crate::DEBUG_LOG.load(std::sync::atomic::Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+pub mod app_state;
+pub mod camera;
+pub mod lib {
+    pub mod cbor;
+    pub mod skia;
+}
+pub mod ai {
+    pub mod army_placement;
+    pub mod cards;
+    pub mod computer_turn;
+    pub mod development;
+    pub mod diplomacy;
+    pub mod draft;
+    pub mod game;
+    pub mod moves;
+    pub mod planner;
+    pub mod possible_move;
+    pub mod purchase;
+    pub mod transposition;
+    pub mod worker;
+}
+pub mod input;
+pub mod keymap;
+
+pub mod model {
+    pub mod card;
+    pub mod city;
+    pub mod connection;
+    pub mod diplomacy;
+    pub mod economy;
+    pub mod history;
+    pub mod location;
+    pub mod math;
+    pub mod player;
+    pub mod profile;
+    pub mod regions;
+    pub mod setup;
+    pub mod simplify;
+    pub mod spatial_index;
+    pub mod territory;
+    pub mod territory_polygon;
+    #[cfg(test)]
+    pub mod test_world;
+    pub mod world_fixed;
+    pub mod world_state;
+}
+pub mod render {
+    pub mod army_placement;
+    pub mod card_hand;
+    pub mod city_selection;
+    pub mod diplomacy;
+    pub mod entry;
+    pub mod lower_panel;
+    pub mod map_style;
+    pub mod minimap;
+    pub mod purchase;
+    pub mod randomising;
+    pub mod region_summary;
+    pub mod setup;
+    pub mod surround;
+    pub mod title_bar;
+    pub mod turn_controls;
+}
+
+use crate::app_state::{AppState, GameMode};
+use std::sync::atomic::AtomicBool;
+
+const ARMIES_PER_SIZE: f32 = 0.1;
+
+pub static DEBUG_LOG: AtomicBool = AtomicBool::new(false);
+
+pub fn next_turn(app_state: &mut AppState) {
+    let world_state = &mut app_state.world_state;
+    let world_fixed = &app_state.world_fixed;
+    world_state.update_scores(world_fixed);
+
+    // Earned a card?
+    if world_state.mode == GameMode::Game {
+        world_state.award_card();
+    }
+
+    // Switch to next player
+    let (turn_done, index) = {
+        let mut index = world_state.current_player;
+        index += 1;
+        if index == world_state.players.len() {
+            index = 0;
+            (true, index)
+        } else {
+            (false, index)
+        }
+    };
+    world_state.current_player = index;
+
+    // Have we finished this phase?
+    if turn_done {
+        match world_state.mode {
+            GameMode::ArmyPlacement => {
+                if world_state.get_current_player().armies_to_assign == 0 {
+                    println!("All armies placed");
+                    world_state.mode = GameMode::Game;
+                }
+            }
+            GameMode::Randomising => {
+                if world_state.cities_to_assign.is_empty() {
+                    println!("All cities assigned");
+                    world_state.mode = GameMode::ArmyPlacement;
+                } else if world_state.cities_to_assign.len() <= world_state.cities_to_draft {
+                    println!("Random share assigned, drafting the rest");
+                    world_state.mode = GameMode::Draft;
+                }
+            }
+            GameMode::Draft => {
+                if world_state.cities_to_assign.is_empty() {
+                    println!("All cities drafted");
+                    world_state.mode = GameMode::ArmyPlacement;
+                }
+            }
+            GameMode::Game => {
+                // Cities grow or decline over the round, then pay their taxes
+                world_state.develop_cities();
+                world_state.collect_income(world_fixed);
+
+                // Time to update armies
+                for player in world_state.players.iter_mut() {
+                    let mut frac = 0.0f32;
+
+                    for (city_index, city) in world_state.cities.iter().enumerate() {
+                        if city.owner == Some(player.index) {
+                            frac += city.size as f32 * ARMIES_PER_SIZE;
+                            frac += world_fixed.cities[city_index].role.reinforcement_bonus();
+                        }
+                    }
+
+                    player.armies_to_assign_fractional += frac;
+                    let frac_int = player.armies_to_assign_fractional as u32;
+                    player.armies_to_assign += frac_int;
+                    player.armies_to_assign_fractional -= frac_int as f32;
+                }
+
+                // Truces run down a round at a time
+                world_state.diplomacy.end_round();
+
+                // Need to calculate victory conditions
+                if let Some(winners) = world_state.shared_victory() {
+                    println!("Allies {:?} share the victory", winners);
+                    world_state.mode = GameMode::End;
+                }
+            }
+            GameMode::Setup | GameMode::End => {}
+        }
+    }
+}
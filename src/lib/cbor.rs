@@ -1,26 +1,48 @@
 use crate::app_state::AppState;
-use crate::lib::skia::Skia;
-use crate::model::city::{select_evenly_spaced_cities, City};
+use crate::model::card::build_deck;
+use crate::model::city::{assign_city_roles, select_evenly_spaced_cities, CityStatic};
 use crate::model::connection::build_connections;
 use crate::model::location::Location;
+use crate::model::regions::build_regions;
+use crate::model::simplify::LEVELS_OF_DETAIL;
+use crate::model::spatial_index::SpatialIndex;
 use crate::model::territory::{get_colour_for_territory_name, Territory};
 use crate::model::territory_polygon::TerritoryPolygon;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
 use ciborium::de::from_reader;
 use ciborium::Value;
-use rand::rng;
 use rand::seq::SliceRandom;
-use std::cell::RefCell;
 use std::collections::BTreeMap;
-use std::rc::Rc;
+use std::sync::Arc;
 
 const REGIONS_CBOR: &[u8] = include_bytes!("../../assets/Regions.cbor");
+pub const CITIES_PER_TERRITORY: usize = 25;
+
+// Maps to choose from on the setup screen, by name
+pub const MAPS: [(&str, &[u8]); 1] = [("World", REGIONS_CBOR)];
+
+pub fn import(app_state: &mut AppState) {
+    let setup = &app_state.setup;
+    let territories = read_territories(setup.map);
+    let world_fixed = Arc::get_mut(&mut app_state.world_fixed).expect("World is already shared");
+    build_world(
+        &mut app_state.world_state,
+        world_fixed,
+        territories,
+        setup.cities_per_territory,
+        setup.minimum_allowed_distance,
+    );
+}
 
-pub fn import(skia: &mut Skia, app_state: &mut AppState) {
+// Territories with their outlines and every candidate city, nothing chosen yet
+pub fn read_territories(map: usize) -> BTreeMap<String, Territory> {
     // Open file
-    let reader = from_reader::<Value, _>(REGIONS_CBOR).expect("Can't load CBOR file");
+    let reader = from_reader::<Value, _>(MAPS[map].1).expect("Can't load CBOR file");
     let mut polygon_count = 0;
     let mut point_count_total = 0;
     let mut cities_count = 0usize;
+    let mut level_point_counts = [0; LEVELS_OF_DETAIL.len()];
 
     // Top level is a map of territories
     let mut territories = BTreeMap::new();
@@ -73,11 +95,14 @@ pub fn import(skia: &mut Skia, app_state: &mut AppState) {
                 }
             }
 
-            // We only want polygons of a minimum complexity
-            if locations.len() >= 64 {
+            // Every polygon down to the smallest island, simplified at each level of detail
+            if locations.len() >= 3 {
                 polygon_count += 1;
 
-                let territory_polygon = TerritoryPolygon::new(skia, territory.colour, locations);
+                let territory_polygon = TerritoryPolygon::new(territory.colour, locations);
+                for (level, points) in territory_polygon.points.iter().enumerate() {
+                    level_point_counts[level] += points;
+                }
                 territory.polygons.push(territory_polygon);
             }
         }
@@ -91,39 +116,62 @@ pub fn import(skia: &mut Skia, app_state: &mut AppState) {
             let longitude = city_details[2].as_float().unwrap() as f32;
             let population: i64 = city_details[3].as_integer().unwrap().try_into().unwrap();
             if !name.eq("Honolulu") && longitude > -140.0 {
-                let city = City::new(name.to_string(), longitude, latitude, population, territory.name.clone());
-                territory.cities.push(Rc::new(RefCell::new(city)));
+                let city = CityStatic::new(name.to_string(), longitude, latitude, population, territory.name.clone());
+                territory.candidates.push(city);
                 cities_count += 1;
             }
         }
-        println!("{} cities", territory.cities.len());
+        println!("{} cities", territory.candidates.len());
 
         territories.insert(territory_name_unwrapped, territory);
     }
 
+    println!("CBOR: Total territories: {}", territories.len());
+    println!("CBOR: Total polygons: {}", polygon_count);
+    println!("CBOR: Total points: {}", point_count_total);
+    for (level, points) in level_point_counts.iter().enumerate() {
+        let saving = 100.0 - *points as f32 * 100.0 / level_point_counts[0].max(1) as f32;
+        println!("CBOR: Level of detail {}: {} points, {:.1}% fewer", level, points, saving);
+    }
+    println!("CBOR: Total cities: {}", cities_count);
+    territories
+}
+
+// Pick the cities, join them up and shuffle them ready to hand out
+pub fn build_world(
+    world_state: &mut WorldState,
+    world_fixed: &mut WorldFixed,
+    territories: BTreeMap<String, Territory>,
+    cities_per_territory: usize,
+    minimum_allowed_distance: f32,
+) {
     // Choose sensible cities for each territory
-    select_evenly_spaced_cities(app_state, 25, territories);
+    select_evenly_spaced_cities(world_state, world_fixed, minimum_allowed_distance, cities_per_territory, territories);
     println!("Cities have been selected");
 
     // Build connections
-    build_connections(&app_state.world_state, &mut app_state.world_fixed);
+    build_connections(world_fixed);
     println!("Connections have been built");
 
-    // And a list of all cities
-    for territory in app_state.world_fixed.territories.values() {
-        for city in territory.cities.iter() {
-            app_state.world_state.cities.push(city.clone());
-            app_state.world_fixed.cities_to_assign.push(city.clone());
-        }
+    // Roles depend on connections
+    assign_city_roles(world_fixed);
+    println!("City roles have been assigned");
+
+    // Everything has its place now, so index where it all is
+    world_fixed.index = SpatialIndex::new(world_fixed);
+    world_fixed.regions = build_regions(world_fixed);
+    println!("City regions have been built");
+
+    // And a list of all cities to hand out, the world state already has them from selection
+    for territory in world_fixed.territories.values() {
+        world_state.cities_to_assign.extend(territory.cities.iter());
     }
 
+    // Card deck, one per city
+    world_state.deck = build_deck(&world_state.cities_to_assign, &mut world_state.rng);
+
     // Shuffle remaining ones randomly
-    let mut rng = rng(); // Create a random number generator
-    app_state.world_fixed.cities_to_assign.shuffle(&mut rng); // Shuffle the vector in place
+    world_state.cities_to_assign.shuffle(&mut world_state.rng); // Shuffle the vector in place
 
-    println!("CBOR: Total territories: {}", app_state.world_fixed.territories.len());
-    println!("CBOR: Total polygons: {}", polygon_count);
-    println!("CBOR: Total points: {}", point_count_total);
-    println!("CBOR: Total cities: {}", cities_count);
-    println!("CBOR: Total cities used: {}", app_state.world_state.cities.len());
+    println!("CBOR: Total cities used: {}", world_state.cities.len());
 }
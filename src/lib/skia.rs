@@ -20,6 +20,8 @@ static NOTO_SANS_SYMBOLS_TTF: &[u8] = include_bytes!("../../assets/NotoSansSymbo
 const NOISE_SKSL: &str = include_str!("../../assets/noise.sksl");
 const HALFTONE_SKSL: &str = include_str!("../../assets/halftone.sksl");
 pub const ELLIPSIS: &str = "\u{2026}";
+const BUTTON_WIDTH: f32 = 328.0 * 0.4 / 2.0;
+const BUTTON_HEIGHT: f32 = 196.0 * 0.4 / 2.0;
 
 pub enum FontFamily {
     EbGaramond,
@@ -33,7 +35,7 @@ pub struct Skia {
     pub drop_shadow: Option<ImageFilter>,
     pub drop_shadow_white: Option<ImageFilter>,
     noise_shader: RuntimeEffect,
-    _halftone_shader: RuntimeEffect,
+    halftone_shader: RuntimeEffect,
     pub surface: Surface,
     pub colour_background: Color,
     pub colour_popup: Color,
@@ -43,7 +45,7 @@ pub struct Skia {
 }
 
 impl Skia {
-    fn make_surface(context: &mut DirectContext, width: i32, height: i32) -> Surface {
+    fn make_surface(context: &mut DirectContext, (width, height): (i32, i32)) -> Surface {
         // Get window size and create a Skia surface from the OpenGL framebuffer
         let fb_info = FramebufferInfo {
             fboid: 0,
@@ -106,11 +108,7 @@ impl Skia {
             drop_shadow_only(Vector::new(1.5, 1.5), (2.0, 2.0), Color::from_argb(64, 255, 255, 255), None, None, None);
 
         // Surface
-        let surface = Skia::make_surface(
-            &mut context,
-            app_state.gfx.width * app_state.gfx.dpi as i32,
-            app_state.gfx.height * app_state.gfx.dpi as i32,
-        );
+        let surface = Skia::make_surface(&mut context, app_state.gfx.pixel_size());
 
         Skia {
             context,
@@ -119,7 +117,7 @@ impl Skia {
             drop_shadow,
             drop_shadow_white,
             noise_shader,
-            _halftone_shader: halftone_shader,
+            halftone_shader,
             colour_background: Color::from_argb(255, 53, 53, 53),
             colour_popup: Color::from_argb(255, 80, 80, 80),
             colour_outline: Color::from_argb(255, 209, 185, 120),
@@ -166,6 +164,11 @@ impl Skia {
         self.get_canvas().draw_rect(Rect::from_xywh(0.0, 0.0, w as f32, h as f32), &paint_background);
     }
 
+    // The window's framebuffer changes size with it, so the surface drawn into it has to as well
+    pub fn resize(&mut self, gfx: &GFXState) {
+        self.surface = Skia::make_surface(&mut self.context, gfx.pixel_size());
+    }
+
     pub fn set_matrix(&mut self, gfx: &GFXState) {
         let canvas = self.get_canvas();
         canvas.save();
@@ -334,7 +337,7 @@ impl Skia {
         self.noise_shader.clone().make_shader(uniforms, &[], None).expect("Make shader failed")
     }
 
-    fn _create_halftone_shader(&mut self, dot_colour: Color, bg_color: Color, dot_radius: f32) -> Shader {
+    fn create_halftone_shader(&mut self, dot_colour: Color, bg_color: Color, dot_radius: f32) -> Shader {
         let uniforms = {
             let mut data = vec![];
 
@@ -350,20 +353,21 @@ impl Skia {
             Data::new_copy(&data)
         };
 
-        self._halftone_shader.clone().make_shader(uniforms, &[], None).expect("Make shader failed")
+        self.halftone_shader.clone().make_shader(uniforms, &[], None).expect("Make shader failed")
     }
 
-    pub fn _halftone(&mut self, dot_color: Color) -> Shader {
-        self._apply_halftone_shader(0.1, dot_color, Color::BLACK)
+    // Dots of a colour, sized by its brightness, over a plain background, spaced in map units
+    pub fn halftone(&mut self, dot_color: Color, bg_color: Color) -> Shader {
+        self.apply_halftone_shader(0.1, dot_color, bg_color)
     }
 
-    fn _apply_halftone_shader(&mut self, dot_radius: f32, dot_color: Color, bg_color: Color) -> Shader {
-        self._create_halftone_shader(dot_color, bg_color, dot_radius)
+    fn apply_halftone_shader(&mut self, dot_radius: f32, dot_color: Color, bg_color: Color) -> Shader {
+        self.create_halftone_shader(dot_color, bg_color, dot_radius)
     }
 
     pub fn button(&mut self, text: &str, app_state: &AppState, xy: Vector) {
-        let width = 328.0 * 0.4 / 2.0;
-        let height = 196.0 * 0.4 / 2.0;
+        let width = BUTTON_WIDTH;
+        let height = BUTTON_HEIGHT;
 
         {
             let canvas = self.get_canvas();
@@ -379,7 +383,7 @@ impl Skia {
         // Background Colour
         let mut paint_click = Paint::default();
         paint_click.set_style(Style::Fill);
-        let rect = skia_safe::Rect::from_xywh(xy.x - width + 1.0, xy.y + 21.4, width * 2.0 - 1.0, height - 3.0);
+        let rect = button_rect(xy);
         if rect.contains(app_state.hover) {
             paint_click.set_color(self.button_hover);
         } else {
@@ -413,6 +417,11 @@ impl Skia {
     }
 }
 
+// Clickable area of a button drawn at the same position
+pub fn button_rect(xy: Vector) -> Rect {
+    Rect::from_xywh(xy.x - BUTTON_WIDTH + 1.0, xy.y + 21.4, BUTTON_WIDTH * 2.0 - 1.0, BUTTON_HEIGHT - 3.0)
+}
+
 pub fn mix_colors(color1: Color, color2: Color, mut ratio: f32) -> Color {
     // Clamp the ratio between 0.0 and 1.0
     ratio = ratio.clamp(0.0, 1.0);
@@ -1,53 +1,23 @@
-mod app_state;
-mod lib {
-    pub mod cbor;
-    pub mod skia;
-}
-mod ai {
-    pub mod army_placement;
-    pub mod computer_turn;
-    pub mod game;
-    pub mod moves;
-    pub mod possible_move;
-}
-mod input;
-
-mod model {
-    pub mod city;
-    pub mod connection;
-    pub mod location;
-    pub mod math;
-    pub mod player;
-    pub mod profile;
-    pub mod territory;
-    pub mod territory_polygon;
-    pub mod world_fixed;
-    pub mod world_state;
-}
-mod render {
-    pub mod army_placement;
-    pub mod city_selection;
-    pub mod entry;
-    pub mod lower_panel;
-    pub mod randomising;
-    pub mod region_summary;
-    pub mod surround;
-    pub mod title_bar;
-}
-
-use crate::ai::computer_turn::computer_turn;
-use crate::app_state::GameMode;
-use crate::input::{handle_mouse_button_down, handle_mouse_button_up, handle_mouse_motion, handle_mouse_wheel};
-use crate::lib::cbor;
-use crate::lib::skia::Skia;
-use crate::model::world_state::WorldState;
-use crate::render::randomising::assign;
-use app_state::AppState;
-use sdl2::video::GLProfile;
+use polis_and_siege::ai::computer_turn::computer_turn;
+use polis_and_siege::ai::draft::draft_pick;
+use polis_and_siege::ai::worker::AiWorker;
+use polis_and_siege::app_state::{AppState, GFXState, GameMode};
+use polis_and_siege::camera::update_camera;
+use polis_and_siege::input::{
+    handle_action, handle_gamepad, handle_mouse_button_down, handle_mouse_button_up, handle_mouse_motion,
+    handle_mouse_wheel,
+};
+use polis_and_siege::keymap::{Action, Gamepad, Keymap};
+use polis_and_siege::lib::skia::Skia;
+use polis_and_siege::model::profile::Difficulty;
+use polis_and_siege::model::world_state::WorldState;
+use polis_and_siege::render::randomising::{assign, draft};
+use polis_and_siege::{next_turn, render, DEBUG_LOG};
+use sdl2::event::WindowEvent;
+use sdl2::video::{FullscreenType, GLProfile, Window};
+use std::sync::atomic::Ordering;
 use std::time::{Duration, Instant};
 
-const ARMIES_PER_SIZE: f32 = 0.05;
-
 fn main() {
     // Initialize SDL2
     let sdl = sdl2::init().unwrap();
@@ -59,7 +29,12 @@ fn main() {
     gl_attr.set_context_version(3, 3); // OpenGL 3.3
 
     // Create an SDL2 window
-    let window = video_subsystem.window("Simulation", 1500, 900).opengl().allow_highdpi().build().unwrap();
+    let mut window =
+        video_subsystem.window("Polis and Siege", 1500, 900).opengl().allow_highdpi().resizable().build().unwrap();
+    window.set_minimum_size(1024, 720).unwrap();
+    if std::env::args().any(|arg| arg == "--fullscreen") {
+        toggle_fullscreen(&mut window);
+    }
 
     // Create an OpenGL context
     let _gl_context = window.gl_create_context().unwrap();
@@ -68,30 +43,34 @@ fn main() {
     // Load OpenGL functions
     gl::load_with(|s| video_subsystem.gl_get_proc_address(s) as *const _);
 
-    // Get display index (typically 0 is the default display)
-    let display_index = 0;
+    // Create an AppState instance using the new method, scaled by how many pixels there are to each window unit
+    let mut app_state = AppState::new(&window, WorldState::default());
+    app_state.world_state.current_player = 0;
 
-    // Get DPI information
-    let mut dpi = 0.0;
-    match video_subsystem.display_dpi(display_index) {
-        Ok((ddpi, hdpi, vdpi)) => {
-            println!("Diagonal DPI: {}", ddpi);
-            println!("Horizontal DPI: {}", hdpi);
-            println!("Vertical DPI: {}", vdpi);
+    // A fixed seed replays the same game, shown on the setup screen and changeable there
+    if let Some(seed) = arg_value("--seed").and_then(|seed| seed.parse().ok()) {
+        println!("Using seed {}", seed);
+        app_state.setup.seed = seed;
+    }
 
-            // Calculate scaling factor
-            dpi = hdpi / 96.0; // 96 DPI is considered the default "normal" DPI
-            println!("Scaling factor: {}", dpi);
-        }
-        Err(e) => {
-            eprintln!("Could not get DPI information: {}", e);
+    // Dice and the computer's thinking written out as the game goes
+    if std::env::args().any(|arg| arg == "--debug") {
+        DEBUG_LOG.store(true, Ordering::Relaxed);
+    }
+
+    // Every computer player at the same level to start with, personalities stay as they are
+    if let Some(difficulty) = arg_value("--difficulty").and_then(|name| Difficulty::from_name(&name)) {
+        println!("Using difficulty {}", difficulty.name());
+        for player in app_state.setup.players.iter_mut() {
+            player.difficulty = difficulty;
         }
     }
-    dpi = dpi.floor();
 
-    // Create an AppState instance using the new method
-    let mut app_state = AppState::new(&window, dpi, WorldState::default());
-    app_state.world_state.current_player = Some(app_state.world_state.players[0].clone());
+    // Keys and controller buttons, changeable from a file
+    let keymap = Keymap::load(arg_value("--keymap").as_deref());
+    let controller_subsystem = sdl.game_controller().unwrap();
+    let mut controllers = Vec::new();
+    let mut gamepad = Gamepad::default();
 
     // Skia and surfaces
     let mut skia = Skia::new(&app_state);
@@ -99,8 +78,8 @@ fn main() {
         skia.flush();
     }
 
-    // Load CBOR data
-    cbor::import(&mut skia, &mut app_state);
+    // The computer players think on their own thread, started once the world has been built
+    let mut worker = None;
 
     // Event pump for SDL2 events
     let mut event_pump = sdl.event_pump().unwrap();
@@ -130,71 +109,115 @@ fn main() {
         }
 
         for event in event_pump.poll_iter() {
+            let mut action = None;
             match event {
-                sdl2::event::Event::Quit { .. }
-
-                // Keys
-                | sdl2::event::Event::KeyDown {
-                    keycode: Some(sdl2::keyboard::Keycode::Escape),
-                    ..
-                } => break 'running,
-                sdl2::event::Event::KeyDown { keycode: Some(key), .. } => {
-                    match key {
-                        // Handle arrow keys
-                        sdl2::keyboard::Keycode::L => {
-                            app_state.show_labels = true;
-                        }
-                        sdl2::keyboard::Keycode::M => {
-                            app_state.show_labels = false;
-                        }
-                        sdl2::keyboard::Keycode::R => {
-                            app_state.reset();
-                        }
-
-                        // Default case for other keys
-                        _ => {}
+                sdl2::event::Event::Quit { .. } => break 'running,
+
+                // Keys and controller buttons, through the keymap
+                sdl2::event::Event::KeyDown { keycode: Some(key), keymod, .. } => {
+                    action = keymap.key_action(key, keymod);
+                }
+                sdl2::event::Event::ControllerButtonDown { button, .. } => {
+                    action = keymap.button_action(button);
+                }
+                sdl2::event::Event::ControllerAxisMotion { axis, value, .. } => {
+                    gamepad.axis_motion(axis, value);
+                }
+
+                // Controllers plugged in, including any there at the start
+                sdl2::event::Event::ControllerDeviceAdded { which, .. } => match controller_subsystem.open(which) {
+                    Ok(controller) => {
+                        println!("Using controller {}", controller.name());
+                        controllers.push(controller);
                     }
+                    Err(e) => eprintln!("Could not open controller {}: {}", which, e),
+                },
+                sdl2::event::Event::ControllerDeviceRemoved { which, .. } => {
+                    controllers.retain(|controller| controller.instance_id() != which);
+                    gamepad = Gamepad::default();
                 }
 
                 // Mouse
-                | sdl2::event::Event::MouseWheel { direction, precise_y, .. } => {
+                sdl2::event::Event::MouseWheel { direction, precise_y, .. } => {
                     handle_mouse_wheel(&mut app_state, direction, precise_y);
                 }
-                | sdl2::event::Event::MouseMotion { x, y, xrel, yrel, .. } => {
+                sdl2::event::Event::MouseMotion { x, y, xrel, yrel, .. } => {
                     handle_mouse_motion(&mut app_state, x, y, xrel, yrel);
                 }
-                | sdl2::event::Event::MouseButtonDown { mouse_btn, .. } => {
-                    handle_mouse_button_down(&mut app_state, mouse_btn);
+                sdl2::event::Event::MouseButtonDown { mouse_btn, clicks, .. } => {
+                    handle_mouse_button_down(&mut app_state, mouse_btn, clicks);
                 }
-                | sdl2::event::Event::MouseButtonUp { mouse_btn, .. } => {
+                sdl2::event::Event::MouseButtonUp { mouse_btn, .. } => {
                     handle_mouse_button_up(&mut app_state, mouse_btn);
                 }
+
+                // Everything is laid out from the window's size, only the surface needs making again
+                sdl2::event::Event::Window { win_event: WindowEvent::SizeChanged(..), .. } => {
+                    app_state.gfx = GFXState::new(&window);
+                    skia.resize(&app_state.gfx);
+                }
+
+                // Edge scrolling stops once the mouse has left the window
+                sdl2::event::Event::Window { win_event: WindowEvent::Enter, .. } => {
+                    app_state.camera.mouse_inside = true;
+                }
+                sdl2::event::Event::Window { win_event: WindowEvent::Leave | WindowEvent::FocusLost, .. } => {
+                    app_state.camera.mouse_inside = false;
+                }
                 _ => {}
             }
+
+            // The window itself is looked after here, everything else by the game
+            match action {
+                Some(Action::Quit) => break 'running,
+                Some(Action::ToggleFullscreen) => toggle_fullscreen(&mut window),
+                Some(action) => handle_action(&mut app_state, action),
+                None => {}
+            }
         }
 
+        handle_gamepad(&mut app_state, &gamepad);
+        update_camera(&mut app_state);
+
         // Game loop
-        if app_state.world_state.mode == GameMode::Randomising && app_state.world_state.current_player.is_some() {
+        if app_state.world_state.mode == GameMode::Randomising {
             let diff = Instant::now() - app_state.selection.last_selection;
             if diff.as_millis() > app_state.selection.assign_speed {
                 app_state.selection.last_selection = Instant::now();
 
-                // Take top item
-                if !app_state.world_fixed.cities_to_assign.is_empty() {
+                // Take top item, leaving any share to be drafted
+                if app_state.world_state.cities_to_assign.len() > app_state.world_state.cities_to_draft {
                     assign(&mut app_state);
                 }
                 next_turn(&mut app_state);
             }
         }
 
+        // Computer players pick their cities at the same pace, humans click on theirs
+        if app_state.world_state.mode == GameMode::Draft {
+            let diff = Instant::now() - app_state.selection.last_selection;
+            if app_state.world_state.cities_to_assign.is_empty() {
+                next_turn(&mut app_state);
+            } else if !app_state.world_state.get_current_player().is_human()
+                && diff.as_millis() > app_state.selection.assign_speed
+            {
+                app_state.selection.last_selection = Instant::now();
+                if let Some(city_index) = draft_pick(&mut app_state.world_state, &app_state.world_fixed) {
+                    draft(&mut app_state, city_index);
+                }
+                next_turn(&mut app_state);
+            }
+        }
+
         // Waiting on AI action?
-        if app_state.world_state.current_player.is_some()
-            && !app_state.world_state.current_player.as_ref().unwrap().borrow().is_human()
-        {
-            match app_state.world_state.mode {
-                GameMode::ArmyPlacement => computer_turn(&mut app_state),
-                GameMode::Game => computer_turn(&mut app_state),
-                _ => {}
+        if app_state.world_state.mode != GameMode::Setup {
+            let worker = worker.get_or_insert_with(|| AiWorker::spawn(app_state.world_fixed.clone()));
+            if !app_state.world_state.get_current_player().is_human() {
+                match app_state.world_state.mode {
+                    GameMode::ArmyPlacement => computer_turn(&mut app_state, worker),
+                    GameMode::Game => computer_turn(&mut app_state, worker),
+                    _ => {}
+                }
             }
         }
 
@@ -203,60 +226,20 @@ fn main() {
     }
 }
 
-pub fn next_turn(app_state: &mut AppState) {
-    let world_state = &mut app_state.world_state;
-    let world_fixed = &app_state.world_fixed;
-    world_state.update_scores(world_fixed);
-
-    // Switch to next player
-    let (turn_done, index) = {
-        let mut index = world_state.current_player.as_ref().unwrap().borrow().index;
-        index += 1;
-        if index == world_state.players.len() {
-            index = 0;
-            (true, index)
-        } else {
-            (false, index)
-        }
+// Filling the screen at its own resolution, or back to a window
+fn toggle_fullscreen(window: &mut Window) {
+    let fullscreen = match window.fullscreen_state() {
+        FullscreenType::Off => FullscreenType::Desktop,
+        _ => FullscreenType::Off,
     };
-    world_state.current_player = Some(world_state.players[index].clone());
-
-    // Have we finished this phase?
-    if turn_done {
-        let current_player = world_state.get_current_player();
-        match world_state.mode {
-            GameMode::ArmyPlacement => {
-                if current_player.borrow().armies_to_assign == 0 {
-                    println!("All armies placed");
-                    world_state.mode = GameMode::Game;
-                }
-            }
-            GameMode::Randomising => {
-                if world_fixed.cities_to_assign.is_empty() {
-                    println!("All cities assigned");
-                    world_state.mode = GameMode::ArmyPlacement;
-                }
-            }
-            GameMode::Game => {
-                // Time to update armies
-                for player in &world_state.players {
-                    let mut frac = 0.0f32;
-
-                    for city in world_state.cities.iter() {
-                        if city.borrow().owner.unwrap() == player.borrow().index {
-                            frac += city.borrow().size as f32 * ARMIES_PER_SIZE;
-                        }
-                    }
-
-                    player.borrow_mut().armies_to_assign_fractional += frac;
-                    let frac_int = player.borrow().armies_to_assign_fractional as u32;
-                    player.borrow_mut().armies_to_assign = frac_int;
-                    player.borrow_mut().armies_to_assign_fractional -= frac_int as f32;
-                }
-
-                // Need to calculate victory conditions
-            }
-            GameMode::End => {}
-        }
+    if let Err(e) = window.set_fullscreen(fullscreen) {
+        eprintln!("Could not change fullscreen: {}", e);
     }
 }
+
+// As in `--seed 1234`, `--difficulty hard` or `--keymap keys.txt`
+fn arg_value(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let position = args.iter().position(|arg| arg == flag)?;
+    args.get(position + 1).cloned()
+}
@@ -0,0 +1,88 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+pub const NUM_WILD_CARDS: usize = 2;
+pub const MAXIMUM_HAND: usize = 5;
+pub const CITY_BONUS_ARMIES: usize = 1;
+const TRADE_IN_BONUSES: [u32; 6] = [2, 3, 4, 5, 6, 8];
+const TRADE_IN_INCREMENT: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardSymbol {
+    Infantry,
+    Cavalry,
+    Artillery,
+    Wild,
+}
+
+impl CardSymbol {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CardSymbol::Infantry => "Infantry",
+            CardSymbol::Cavalry => "Cavalry",
+            CardSymbol::Artillery => "Artillery",
+            CardSymbol::Wild => "Wild",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Card {
+    pub symbol: CardSymbol,
+    pub city: Option<usize>,
+}
+
+// One card per city, symbols dealt round-robin, plus a couple of wild cards
+pub fn build_deck(cities: &[usize], rng: &mut impl Rng) -> Vec<Card> {
+    let symbols = [CardSymbol::Infantry, CardSymbol::Cavalry, CardSymbol::Artillery];
+    let mut deck = Vec::new();
+    for &index in cities {
+        deck.push(Card { symbol: symbols[index % symbols.len()], city: Some(index) });
+    }
+    for _ in 0..NUM_WILD_CARDS {
+        deck.push(Card { symbol: CardSymbol::Wild, city: None });
+    }
+    deck.shuffle(rng);
+    deck
+}
+
+// Three of a kind or one of each, wild cards standing in for anything
+pub fn is_set(cards: [&Card; 3]) -> bool {
+    let symbols: Vec<CardSymbol> =
+        cards.iter().map(|card| card.symbol).filter(|symbol| *symbol != CardSymbol::Wild).collect();
+    match symbols.len() {
+        0..=2 => true,
+        _ => {
+            (symbols[0] == symbols[1] && symbols[1] == symbols[2])
+                || (symbols[0] != symbols[1] && symbols[1] != symbols[2] && symbols[0] != symbols[2])
+        }
+    }
+}
+
+pub fn all_sets(hand: &[Card]) -> Vec<[usize; 3]> {
+    let mut results = Vec::new();
+    for a in 0..hand.len() {
+        for b in a + 1..hand.len() {
+            for c in b + 1..hand.len() {
+                if is_set([&hand[a], &hand[b], &hand[c]]) {
+                    results.push([a, b, c]);
+                }
+            }
+        }
+    }
+    results
+}
+
+pub fn find_set(hand: &[Card]) -> Option<[usize; 3]> {
+    all_sets(hand).first().copied()
+}
+
+// Bonus grows with every set traded in by anyone
+pub fn trade_in_bonus(sets_traded: usize) -> u32 {
+    if sets_traded < TRADE_IN_BONUSES.len() {
+        TRADE_IN_BONUSES[sets_traded]
+    } else {
+        let extra = (sets_traded - TRADE_IN_BONUSES.len() + 1) as u32;
+        TRADE_IN_BONUSES[TRADE_IN_BONUSES.len() - 1] + extra * TRADE_IN_INCREMENT
+    }
+}
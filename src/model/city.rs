@@ -1,14 +1,65 @@
-use crate::app_state::AppState;
 use crate::model::connection::ConnectionArc;
+use crate::model::economy::{GOLD_PER_SIZE, MAXIMUM_FORTIFICATION};
 use crate::model::location::{calculate_distance, Location};
 use crate::model::profile::Profile;
 use crate::model::territory::Territory;
 use crate::model::world_fixed::WorldFixed;
 use crate::model::world_state::WorldState;
 use petgraph::graph::NodeIndex;
-use std::cell::RefCell;
 use std::collections::BTreeMap;
-use std::rc::Rc;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CityRole {
+    #[default]
+    Town,
+    Acropolis,
+    Agropolis,
+    Metropolis,
+}
+
+impl CityRole {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CityRole::Town => "Town",
+            CityRole::Acropolis => "Acropolis",
+            CityRole::Agropolis => "Agropolis",
+            CityRole::Metropolis => "Metropolis",
+        }
+    }
+
+    // Added to the defender's dice when attacked
+    pub fn defence_bonus(&self) -> u8 {
+        match self {
+            CityRole::Acropolis => ACROPOLIS_DEFENCE_BONUS,
+            _ => 0,
+        }
+    }
+
+    // Extra fractional armies per turn on top of the size based income
+    pub fn reinforcement_bonus(&self) -> f32 {
+        match self {
+            CityRole::Agropolis => AGROPOLIS_REINFORCEMENTS,
+            _ => 0.0,
+        }
+    }
+
+    // Extra gold per turn on top of the size based income
+    pub fn gold_bonus(&self) -> u32 {
+        match self {
+            CityRole::Metropolis => METROPOLIS_GOLD,
+            _ => 0,
+        }
+    }
+
+    pub fn score(&self, profile: &Profile) -> f32 {
+        match self {
+            CityRole::Town => 0.0,
+            CityRole::Acropolis => profile.acropolis_value,
+            CityRole::Agropolis => profile.agropolis_value,
+            CityRole::Metropolis => profile.metropolis_value,
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone)]
 pub struct CityStatic {
@@ -19,24 +70,34 @@ pub struct CityStatic {
     pub population: i64,
     pub location: Location,
     pub index: usize,
+    pub role: CityRole,
 }
 
-#[derive(Debug, Clone)]
-pub struct City {
-    pub statics: CityStaticRR,
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CityState {
     pub size: u8,
+    pub growth: f32,
+    pub besieged: bool,
+    pub fortification: u8,
     pub armies: usize,
+    pub cavalry: usize,
     pub owner: Option<usize>,
 }
 
-pub type CityStaticRR = Rc<RefCell<CityStatic>>;
-pub type CityRR = Rc<RefCell<City>>;
-
 pub const SIZE: f32 = 3.0;
 pub const SIZE_SELECTED: f32 = 4.0;
 pub const MAXIMUM_LABEL_WIDTH: f32 = 32.0;
+pub const ROLE_ICON_SIZE: f32 = 5.0;
+pub const ACROPOLIS_DEFENCE_BONUS: u8 = 1;
+pub const AGROPOLIS_REINFORCEMENTS: f32 = 0.15;
+pub const METROPOLIS_GOLD: u32 = 3;
+const AGROPOLIS_PER_TERRITORY: usize = 3;
+pub const MAXIMUM_CITY_SIZE: u8 = 8;
+pub const UPGRADE_COST_PER_SIZE: u32 = 2;
+const GROWTH_RATE: f32 = 0.5;
+const SIEGE_DECAY: f32 = 0.5;
 
-impl City {
+impl CityStatic {
     pub fn new(
         name: String,
         longitude: f32,
@@ -44,50 +105,104 @@ impl City {
         population: i64,
         territory_name: String,
     ) -> Self {
-        let size = match population {
+        Self {
+            territory_name,
+            name,
+            location: Location::new(longitude, latitude),
+            population,
+            ..CityStatic::default()
+        }
+    }
+}
+
+impl CityState {
+    pub fn new(statics: &CityStatic) -> Self {
+        let size = match statics.population {
             0..150000 => 1,
             150000..500000 => 2,
             500000..2500000 => 3,
             2500000..5000000 => 4,
             _ => 5,
         };
-        let statics = Rc::new(RefCell::new(CityStatic {
-            territory_name,
-            name,
-            location: Location::new(longitude, latitude),
-            population,
-            ..CityStatic::default()
-        }));
 
         Self {
-            statics,
             size,
+            growth: 0.0,
+            besieged: false,
+            fortification: 0,
             armies: 1,
+            cavalry: 0,
             owner: None,
         }
     }
 
-    pub fn full_clone(city_rr: &CityRR) -> CityRR {
-        let cloned_raw = city_rr.borrow().clone();
-        Rc::new(RefCell::new(cloned_raw))
+    // Smaller cities grow quicker, anything attacked this round declines instead
+    pub fn develop(&mut self) {
+        if self.besieged {
+            self.growth -= SIEGE_DECAY;
+            if self.growth < 0.0 {
+                if self.size > 1 {
+                    self.size -= 1;
+                    self.growth += 1.0;
+                } else {
+                    self.growth = 0.0;
+                }
+            }
+            self.besieged = false;
+        } else if self.size < MAXIMUM_CITY_SIZE {
+            self.growth += GROWTH_RATE / self.size as f32;
+            if self.growth >= 1.0 {
+                self.size += 1;
+                self.growth = 0.0;
+            }
+        }
+    }
+
+    // Sacked cities lose a size, any progress and their walls
+    pub fn capture(&mut self) {
+        self.size = self.size.saturating_sub(1).max(1);
+        self.growth = 0.0;
+        self.fortification = 0;
+        self.cavalry = 0;
+    }
+
+    pub fn income(&self, statics: &CityStatic) -> u32 {
+        self.size as u32 * GOLD_PER_SIZE + statics.role.gold_bonus()
+    }
+
+    pub fn defence_bonus(&self, statics: &CityStatic) -> u8 {
+        statics.role.defence_bonus() + self.fortification
     }
 
-    pub fn score(&self, world_state: &WorldState, world_fixed: &WorldFixed, profile: &Profile) -> f32 {
+    pub fn can_fortify(&self) -> bool {
+        self.fortification < MAXIMUM_FORTIFICATION
+    }
+
+    pub fn upgrade_cost(&self) -> u32 {
+        self.size as u32 * UPGRADE_COST_PER_SIZE
+    }
+
+    pub fn can_upgrade(&self) -> bool {
+        self.size < MAXIMUM_CITY_SIZE
+    }
+
+    pub fn score(&self, statics: &CityStatic, world_state: &WorldState, profile: &Profile) -> f32 {
         let mut score = 0f32;
-        score += self.size as f32 * profile.city_size_multiplier;
+        score += (self.size as f32 + self.growth) * profile.city_size_multiplier;
         score += self.armies as f32 * profile.army_multiplier;
+        score += self.cavalry as f32 * profile.army_multiplier;
+        score += self.fortification as f32 * profile.fortification_value;
+        score += statics.role.score(profile);
 
         // Logic for additional armies, extra score if bordering enemy concentrations
-        for connection in self.statics.borrow().connections.iter() {
-            let other_city = &world_state.cities[connection.city2];
-            let other_city_owner = other_city.borrow().owner;
-            let other_city_territory = other_city.borrow().statics.borrow().territory_name.clone();
+        for connection in statics.connections.iter() {
+            let other_city_owner = world_state.cities[connection.city2].owner;
             score += self.armies as f32 * profile.army_multiplier;
 
             // If enemy city, add a boost
             if other_city_owner.is_some() && other_city_owner != self.owner {
                 score += self.armies as f32 * profile.army_bordering;
-                if other_city_territory.eq(&self.statics.borrow().territory_name) {
+                if connection.same_territory {
                     score += self.armies as f32 * profile.army_same_territory;
                 }
             }
@@ -99,33 +214,28 @@ impl City {
 
 // Function to select evenly spaced cities
 pub fn select_evenly_spaced_cities(
-    app_state: &mut AppState,
+    world_state: &mut WorldState,
+    world_fixed: &mut WorldFixed,
+    minimum_allowed_distance: f32,
     num_cities_to_select: usize,
     territories: BTreeMap<String, Territory>,
 ) {
-    let world_state = &mut app_state.world_state;
-    let world_fixed = &mut app_state.world_fixed;
-    let mut city_index = 0usize;
     for (territory_name, mut territory) in territories {
         let mut selected_cities = Vec::new();
 
         // Sort the cities by population (largest first)
-        territory.cities.sort_by(|a, b| {
-            b.borrow().statics.borrow().population.cmp(&a.borrow().statics.borrow().population)
-        });
+        let mut candidates = std::mem::take(&mut territory.candidates);
+        candidates.sort_by(|a, b| b.population.cmp(&a.population));
 
         // Loop through all cities
-        for city in &territory.cities {
+        for mut city in candidates {
             let mut want = true;
 
             // Check distance to already selected cities
             for existing in world_fixed.city_locations.iter() {
-                if existing.p != city.borrow().statics.borrow().location.p {
-                    let dist = calculate_distance(
-                        &city.borrow().statics.borrow().location,
-                        existing,
-                    );
-                    if dist <= app_state.selection.minimum_allowed_distance {
+                if existing.p != city.location.p {
+                    let dist = calculate_distance(&city.location, existing);
+                    if dist <= minimum_allowed_distance {
                         want = false;
                         break;
                     }
@@ -134,12 +244,11 @@ pub fn select_evenly_spaced_cities(
 
             // If the city is far enough, select it
             if want {
-                city.borrow().statics.borrow_mut().index = city_index;
-                city_index += 1;
-                world_fixed
-                    .city_locations
-                    .push(city.borrow().statics.borrow().location.clone());
-                selected_cities.push(city.clone());
+                city.index = world_fixed.cities.len();
+                world_fixed.city_locations.push(city.location.clone());
+                selected_cities.push(city.index);
+                world_state.cities.push(CityState::new(&city));
+                world_fixed.cities.push(city);
 
                 // Stop if we have selected enough cities
                 if selected_cities.len() >= num_cities_to_select {
@@ -149,10 +258,37 @@ pub fn select_evenly_spaced_cities(
         }
 
         territory.cities = selected_cities;
-        for city in &territory.cities {
-            world_state.cities.push(city.clone());
-        }
         let contained = territory.containerise(world_fixed);
         world_fixed.territories.insert(territory_name, contained);
     }
 }
+
+// Largest city of each territory is its metropolis, border cities are fortified and the smallest few farm
+pub fn assign_city_roles(world_fixed: &mut WorldFixed) {
+    for territory in world_fixed.territories.values() {
+        let mut cities = territory.cities.clone();
+        cities.sort_by(|a, b| world_fixed.cities[*b].population.cmp(&world_fixed.cities[*a].population));
+
+        for (index, city_index) in cities.iter().enumerate() {
+            let statics = &mut world_fixed.cities[*city_index];
+            let border = statics.connections.iter().any(|connection| !connection.same_territory);
+            statics.role = if index == 0 {
+                CityRole::Metropolis
+            } else if border {
+                CityRole::Acropolis
+            } else {
+                CityRole::Town
+            };
+        }
+
+        let towns: Vec<usize> = cities
+            .iter()
+            .rev()
+            .filter(|city_index| world_fixed.cities[**city_index].role == CityRole::Town)
+            .copied()
+            .collect();
+        for city_index in towns.into_iter().take(AGROPOLIS_PER_TERRITORY) {
+            world_fixed.cities[city_index].role = CityRole::Agropolis;
+        }
+    }
+}
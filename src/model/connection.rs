@@ -1,12 +1,10 @@
 use crate::model::location::calculate_distance;
 use crate::model::territory::TerritoryArc;
 use crate::model::world_fixed::WorldFixed;
-use crate::model::world_state::WorldState;
 use petgraph::algo::min_spanning_tree;
 use petgraph::data::FromElements;
 use petgraph::prelude::UnGraph;
 use std::collections::BTreeMap;
-use std::rc::Rc;
 use std::sync::Arc;
 
 pub const LINE_WIDTH: f32 = 0.25;
@@ -22,7 +20,7 @@ pub struct Connection {
 pub type ConnectionArc = Arc<Connection>;
 
 fn build_territory_connections(
-    world_state: &WorldState,
+    world_fixed: &mut WorldFixed,
     connections: &mut Vec<ConnectionArc>,
     territory1: &TerritoryArc,
     territory2: &TerritoryArc,
@@ -33,28 +31,16 @@ fn build_territory_connections(
 
     for city1 in territory1.cities.iter() {
         for city2 in territory2.cities.iter() {
-            if !Rc::ptr_eq(city1, city2) {
-                let distance = calculate_distance(
-                    &city1.borrow().statics.borrow().location,
-                    &city2.borrow().statics.borrow().location,
-                );
+            if city1 != city2 {
+                let distance =
+                    calculate_distance(&world_fixed.cities[*city1].location, &world_fixed.cities[*city2].location);
                 m1.insert(
                     distance as usize,
-                    Arc::new(Connection {
-                        city1: city1.borrow().statics.borrow().index,
-                        city2: city2.borrow().statics.borrow().index,
-                        render: true,
-                        same_territory: false,
-                    }),
+                    Arc::new(Connection { city1: *city1, city2: *city2, render: true, same_territory: false }),
                 );
                 m2.insert(
                     distance as usize,
-                    Arc::new(Connection {
-                        city2: city1.borrow().statics.borrow().index,
-                        city1: city2.borrow().statics.borrow().index,
-                        render: false,
-                        same_territory: false,
-                    }),
+                    Arc::new(Connection { city2: *city1, city1: *city2, render: false, same_territory: false }),
                 );
             }
         }
@@ -67,17 +53,17 @@ fn build_territory_connections(
     for _ in 0..num_connections {
         let v = iter.next().unwrap();
         connections.push(v.1.clone());
-        world_state.cities[v.1.city1].borrow().statics.borrow_mut().connections.push(v.1.clone());
+        world_fixed.cities[v.1.city1].connections.push(v.1.clone());
     }
     let mut iter = m2.iter();
     for _ in 0..num_connections {
         let v = iter.next().unwrap();
         connections.push(v.1.clone());
-        world_state.cities[v.1.city2].borrow().statics.borrow_mut().connections.push(v.1.clone());
+        world_fixed.cities[v.1.city2].connections.push(v.1.clone());
     }
 }
 
-pub fn build_connections(world_state: &WorldState, world_fixed: &mut WorldFixed) {
+pub fn build_connections(world_fixed: &mut WorldFixed) {
     let mut connections = Vec::new();
     let connections_in = world_fixed.territories.clone();
     for territory in connections_in.values() {
@@ -85,24 +71,18 @@ pub fn build_connections(world_state: &WorldState, world_fixed: &mut WorldFixed)
         let cities = &territory.cities;
 
         // Cities
-        for (city_index, city) in cities.iter().enumerate() {
-            let node = graph.add_node(city);
-            city.borrow().statics.borrow_mut().node = node;
+        for city_index in cities.iter() {
+            let node = graph.add_node(*city_index);
+            world_fixed.cities[*city_index].node = node;
         }
 
         // Distances
         for city1 in cities.iter() {
             for city2 in cities.iter() {
-                if !Rc::ptr_eq(city1, city2) {
-                    let distance = calculate_distance(
-                        &city1.borrow().statics.borrow().location,
-                        &city2.borrow().statics.borrow().location,
-                    );
-                    graph.add_edge(
-                        city1.borrow().statics.borrow().node,
-                        city2.borrow().statics.borrow().node,
-                        distance,
-                    );
+                if city1 != city2 {
+                    let distance =
+                        calculate_distance(&world_fixed.cities[*city1].location, &world_fixed.cities[*city2].location);
+                    graph.add_edge(world_fixed.cities[*city1].node, world_fixed.cities[*city2].node, distance);
                 }
             }
         }
@@ -115,45 +95,36 @@ pub fn build_connections(world_state: &WorldState, world_fixed: &mut WorldFixed)
             let target = graph[edge.target()];
 
             // Create connections, but only render one
-            let connection1 = Arc::new(Connection {
-                city1: source.borrow().statics.borrow().index,
-                city2: target.borrow().statics.borrow().index,
-                render: true,
-                same_territory: true,
-            });
-            let connection2 = Arc::new(Connection {
-                city1: target.borrow().statics.borrow().index,
-                city2: source.borrow().statics.borrow().index,
-                render: false,
-                same_territory: true,
-            });
+            let connection1 = Arc::new(Connection { city1: source, city2: target, render: true, same_territory: true });
+            let connection2 =
+                Arc::new(Connection { city1: target, city2: source, render: false, same_territory: true });
             connections.push(connection1.clone());
             connections.push(connection2.clone());
-            source.borrow().statics.borrow_mut().connections.push(connection1);
-            target.borrow().statics.borrow_mut().connections.push(connection2);
+            world_fixed.cities[source].connections.push(connection1);
+            world_fixed.cities[target].connections.push(connection2);
         }
     }
     println!("Built intra-territory connections");
 
-    let na = world_fixed.territories.get("North America").unwrap();
-    let la = world_fixed.territories.get("Latin America and the Caribbean").unwrap();
-    let eu = world_fixed.territories.get("Europe").unwrap();
-    let me = world_fixed.territories.get("Middle East and North Africa").unwrap();
-    let ee = world_fixed.territories.get("Eastern Europe").unwrap();
-    let af = world_fixed.territories.get("Sub-Saharan Africa").unwrap();
-    let asia = world_fixed.territories.get("Asia").unwrap();
-    let au = world_fixed.territories.get("Australia and New Zealand").unwrap();
+    let na = &connections_in["North America"];
+    let la = &connections_in["Latin America and the Caribbean"];
+    let eu = &connections_in["Europe"];
+    let me = &connections_in["Middle East and North Africa"];
+    let ee = &connections_in["Eastern Europe"];
+    let af = &connections_in["Sub-Saharan Africa"];
+    let asia = &connections_in["Asia"];
+    let au = &connections_in["Australia and New Zealand"];
 
     // Now we need inter-territory connections
-    build_territory_connections(world_state, &mut connections, na, la, 2);
-    build_territory_connections(world_state, &mut connections, na, eu, 1);
-    build_territory_connections(world_state, &mut connections, eu, ee, 2);
-    build_territory_connections(world_state, &mut connections, eu, me, 2);
-    build_territory_connections(world_state, &mut connections, af, me, 2);
-    build_territory_connections(world_state, &mut connections, af, la, 1);
-    build_territory_connections(world_state, &mut connections, asia, me, 2);
-    build_territory_connections(world_state, &mut connections, asia, au, 1);
-    build_territory_connections(world_state, &mut connections, asia, ee, 2);
+    build_territory_connections(world_fixed, &mut connections, na, la, 2);
+    build_territory_connections(world_fixed, &mut connections, na, eu, 1);
+    build_territory_connections(world_fixed, &mut connections, eu, ee, 2);
+    build_territory_connections(world_fixed, &mut connections, eu, me, 2);
+    build_territory_connections(world_fixed, &mut connections, af, me, 2);
+    build_territory_connections(world_fixed, &mut connections, af, la, 1);
+    build_territory_connections(world_fixed, &mut connections, asia, me, 2);
+    build_territory_connections(world_fixed, &mut connections, asia, au, 1);
+    build_territory_connections(world_fixed, &mut connections, asia, ee, 2);
     println!("Built inter-territory connections");
 
     world_fixed.connections = connections;
@@ -0,0 +1,135 @@
+// How long a truce can be offered for, in rounds
+pub const TRUCE_LENGTHS: [u32; 3] = [3, 5, 10];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TreatyKind {
+    Truce,
+    Alliance,
+}
+
+impl TreatyKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TreatyKind::Truce => "Truce",
+            TreatyKind::Alliance => "Alliance",
+        }
+    }
+}
+
+// Between two players, a truce runs out after its turns while an alliance lasts the game
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Treaty {
+    pub players: (usize, usize),
+    pub kind: TreatyKind,
+    pub turns_left: u32,
+}
+
+// The turns only matter for a truce
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Proposal {
+    pub from: usize,
+    pub to: usize,
+    pub kind: TreatyKind,
+    pub turns: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiplomacyAction {
+    Propose(TreatyKind, u32),
+    Accept,
+    Decline,
+}
+
+impl DiplomacyAction {
+    pub fn name(&self) -> String {
+        match self {
+            DiplomacyAction::Propose(TreatyKind::Truce, turns) => format!("Truce {}", turns),
+            DiplomacyAction::Propose(TreatyKind::Alliance, _) => "Alliance".to_string(),
+            DiplomacyAction::Accept => "Accept".to_string(),
+            DiplomacyAction::Decline => "Decline".to_string(),
+        }
+    }
+}
+
+// Treaties in force and offers waiting on an answer. Part of the world state, so undo and the AI's copies
+// carry it
+#[derive(Debug, Clone, Default)]
+pub struct Diplomacy {
+    pub treaties: Vec<Treaty>,
+    pub proposals: Vec<Proposal>,
+}
+
+fn pair(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+impl Diplomacy {
+    pub fn treaty(&self, a: usize, b: usize) -> Option<&Treaty> {
+        self.treaties.iter().find(|treaty| treaty.players == pair(a, b))
+    }
+
+    pub fn at_peace(&self, a: usize, b: usize) -> bool {
+        self.treaty(a, b).is_some()
+    }
+
+    pub fn allied(&self, a: usize, b: usize) -> bool {
+        a == b || self.treaty(a, b).is_some_and(|treaty| treaty.kind == TreatyKind::Alliance)
+    }
+
+    pub fn proposal(&self, from: usize, to: usize) -> Option<&Proposal> {
+        self.proposals.iter().find(|proposal| proposal.from == from && proposal.to == to)
+    }
+
+    pub fn propose(&mut self, from: usize, to: usize, kind: TreatyKind, turns: u32) {
+        if self.proposal(from, to).is_none() {
+            self.proposals.push(Proposal { from, to, kind, turns });
+        }
+    }
+
+    // Any offers between the two are settled either way
+    pub fn answer(&mut self, from: usize, to: usize, accept: bool) {
+        let Some(proposal) = self.proposal(from, to).copied() else {
+            return;
+        };
+        self.proposals.retain(|other| pair(other.from, other.to) != pair(from, to));
+        if accept {
+            self.sign(from, to, proposal.kind, proposal.turns);
+        }
+    }
+
+    // Replaces whatever was agreed before, so a truce can become an alliance
+    pub fn sign(&mut self, a: usize, b: usize, kind: TreatyKind, turns: u32) {
+        self.treaties.retain(|treaty| treaty.players != pair(a, b));
+        self.treaties.push(Treaty { players: pair(a, b), kind, turns_left: turns });
+    }
+
+    // What the viewer can do about another player, offers to them first, then a truce of each length
+    pub fn actions(&self, viewer: usize, other: usize) -> Vec<DiplomacyAction> {
+        if self.proposal(other, viewer).is_some() {
+            return vec![DiplomacyAction::Accept, DiplomacyAction::Decline];
+        }
+        if self.proposal(viewer, other).is_some() {
+            return Vec::new();
+        }
+        let alliance = DiplomacyAction::Propose(TreatyKind::Alliance, 0);
+        match self.treaty(viewer, other).map(|treaty| treaty.kind) {
+            None => TRUCE_LENGTHS
+                .iter()
+                .map(|turns| DiplomacyAction::Propose(TreatyKind::Truce, *turns))
+                .chain([alliance])
+                .collect(),
+            Some(TreatyKind::Truce) => vec![alliance],
+            Some(TreatyKind::Alliance) => Vec::new(),
+        }
+    }
+
+    // A round has passed, truces count down and lapse
+    pub fn end_round(&mut self) {
+        for treaty in self.treaties.iter_mut() {
+            if treaty.kind == TreatyKind::Truce {
+                treaty.turns_left = treaty.turns_left.saturating_sub(1);
+            }
+        }
+        self.treaties.retain(|treaty| treaty.kind == TreatyKind::Alliance || treaty.turns_left > 0);
+    }
+}
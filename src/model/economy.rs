@@ -0,0 +1,39 @@
+use crate::model::city::CityState;
+
+pub const GOLD_PER_SIZE: u32 = 1;
+pub const REGIMENT_COST: u32 = 3;
+pub const FORTIFY_COST: u32 = 4;
+pub const CAVALRY_COST: u32 = 5;
+pub const MAXIMUM_FORTIFICATION: u8 = 2;
+pub const MAXIMUM_CAVALRY_PER_CITY: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purchase {
+    Regiment,
+    Upgrade,
+    Fortify,
+    Cavalry,
+}
+
+pub const PURCHASES: [Purchase; 4] = [Purchase::Upgrade, Purchase::Fortify, Purchase::Cavalry, Purchase::Regiment];
+
+impl Purchase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Purchase::Regiment => "Regiment",
+            Purchase::Upgrade => "Upgrade",
+            Purchase::Fortify => "Fortify",
+            Purchase::Cavalry => "Cavalry",
+        }
+    }
+
+    // None when the city can't take any more of it
+    pub fn cost(&self, city: &CityState) -> Option<u32> {
+        match self {
+            Purchase::Regiment => Some(REGIMENT_COST),
+            Purchase::Upgrade => city.can_upgrade().then(|| city.upgrade_cost()),
+            Purchase::Fortify => city.can_fortify().then_some(FORTIFY_COST),
+            Purchase::Cavalry => (city.cavalry < MAXIMUM_CAVALRY_PER_CITY).then_some(CAVALRY_COST),
+        }
+    }
+}
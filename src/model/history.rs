@@ -0,0 +1,121 @@
+use crate::ai::moves::{Move, MoveResult};
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+
+// Something a human did on their go
+#[derive(Debug, Clone)]
+pub enum Command {
+    Move(Move),
+    TradeCards([usize; 3]),
+}
+
+impl Command {
+    fn apply(&self, world_state: &mut WorldState, world_fixed: &WorldFixed) -> MoveResult {
+        match self {
+            Command::Move(the_move) => the_move.do_move(world_state, world_fixed),
+            Command::TradeCards(set) => {
+                world_state.trade_cards(world_state.get_current_player_index(), *set);
+                MoveResult::Done
+            }
+        }
+    }
+}
+
+// The human's actions since they last confirmed, each with the world as it was before so it can be put
+// back. Anything that rolled dice can't be taken back, so it clears what went before, and anything refused
+// isn't kept at all
+#[derive(Debug, Default)]
+pub struct History {
+    done: Vec<(Command, WorldState)>,
+    undone: Vec<Command>,
+}
+
+impl History {
+    pub fn apply(&mut self, world_state: &mut WorldState, world_fixed: &WorldFixed, command: Command) {
+        self.undone.clear();
+        self.record(world_state, world_fixed, command);
+    }
+
+    fn record(&mut self, world_state: &mut WorldState, world_fixed: &WorldFixed, command: Command) {
+        let before = world_state.clone();
+        match command.apply(world_state, world_fixed) {
+            MoveResult::Refused => {}
+            MoveResult::Done => self.done.push((command, before)),
+            MoveResult::Rolled => self.clear(),
+        }
+    }
+
+    pub fn undo(&mut self, world_state: &mut WorldState) -> bool {
+        let Some((command, before)) = self.done.pop() else {
+            return false;
+        };
+        *world_state = before;
+        self.undone.push(command);
+        true
+    }
+
+    pub fn redo(&mut self, world_state: &mut WorldState, world_fixed: &WorldFixed) -> bool {
+        let Some(command) = self.undone.pop() else {
+            return false;
+        };
+        self.record(world_state, world_fixed, command);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::army_placement::MAXIMUM_ARMIES_PER_CITY;
+    use crate::model::test_world::line_world;
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let (mut world_state, world_fixed) = line_world();
+        world_state.players[0].armies_to_assign = 2;
+        let start = world_state.clone();
+        let mut history = History::default();
+        history.apply(&mut world_state, &world_fixed, Command::Move(Move::new_place_army(0)));
+        history.apply(&mut world_state, &world_fixed, Command::Move(Move::new_place_army(1)));
+        let after = world_state.clone();
+
+        assert!(history.undo(&mut world_state));
+        assert!(history.undo(&mut world_state));
+        assert!(!history.undo(&mut world_state));
+        assert_eq!(world_state.cities, start.cities);
+        assert_eq!(world_state.players[0].armies_to_assign, 2);
+
+        assert!(history.redo(&mut world_state, &world_fixed));
+        assert!(history.redo(&mut world_state, &world_fixed));
+        assert!(!history.redo(&mut world_state, &world_fixed));
+        assert_eq!(world_state.cities, after.cities);
+        assert_eq!(world_state.players[0].armies_to_assign, 0);
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn refused_moves_are_not_kept_and_dice_clear_the_rest() {
+        let (mut world_state, world_fixed) = line_world();
+        world_state.players[0].armies_to_assign = 1;
+        world_state.cities[3].armies = MAXIMUM_ARMIES_PER_CITY;
+        let mut history = History::default();
+
+        // No room in a full city
+        history.apply(&mut world_state, &world_fixed, Command::Move(Move::new_place_army(3)));
+        assert!(!history.can_undo());
+
+        history.apply(&mut world_state, &world_fixed, Command::Move(Move::new_place_army(2)));
+        assert!(history.can_undo());
+        history.apply(&mut world_state, &world_fixed, Command::Move(Move::new_attack_city(3, 4)));
+        assert!(!history.can_undo());
+    }
+}
@@ -1,11 +1,163 @@
-#[derive(Debug, Clone)]
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Brutal,
+}
+
+pub const DIFFICULTIES: [Difficulty; 4] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Brutal];
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Brutal => "Brutal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        DIFFICULTIES.into_iter().find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    fn search_depth(&self) -> usize {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 3,
+            Difficulty::Hard => 4,
+            Difficulty::Brutal => 5,
+        }
+    }
+
+    fn thinking_time(&self) -> Duration {
+        match self {
+            Difficulty::Easy => Duration::from_millis(150),
+            Difficulty::Normal => Duration::from_millis(500),
+            Difficulty::Hard => Duration::from_millis(1000),
+            Difficulty::Brutal => Duration::from_millis(2000),
+        }
+    }
+
+    // How often a decision is deliberately fumbled
+    fn mistake_chance(&self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.3,
+            Difficulty::Normal => 0.05,
+            Difficulty::Hard | Difficulty::Brutal => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Personality {
+    #[default]
+    Balanced,
+    Aggressor,
+    Turtle,
+    TerritoryHoarder,
+    Opportunist,
+}
+
+pub const PERSONALITIES: [Personality; 5] = [
+    Personality::Balanced,
+    Personality::Aggressor,
+    Personality::Turtle,
+    Personality::TerritoryHoarder,
+    Personality::Opportunist,
+];
+
+impl Personality {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Personality::Balanced => "Balanced",
+            Personality::Aggressor => "Aggressor",
+            Personality::Turtle => "Turtle",
+            Personality::TerritoryHoarder => "Territory Hoarder",
+            Personality::Opportunist => "Opportunist",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Profile {
     pub human: bool,
+    pub personality: Personality,
+    pub difficulty: Difficulty,
     pub search_depth: usize,
+    pub thinking_time: Duration,
+    pub mistake_chance: f32,
     pub city_size_multiplier: f32,
     pub army_multiplier: f32,
     pub army_same_territory: f32,
     pub army_bordering: f32,
     pub minimum_armies: usize,
     pub attack_delta_multiplier: f32,
+    pub acropolis_value: f32,
+    pub agropolis_value: f32,
+    pub metropolis_value: f32,
+    pub fortification_value: f32,
+    pub territory_value: f32,
+}
+
+impl Profile {
+    // Difficulty sets how hard it looks and how often it slips, personality what it goes after
+    pub fn new(personality: Personality, difficulty: Difficulty) -> Self {
+        let mut profile = Profile {
+            human: false,
+            personality,
+            difficulty,
+            search_depth: difficulty.search_depth(),
+            thinking_time: difficulty.thinking_time(),
+            mistake_chance: difficulty.mistake_chance(),
+            city_size_multiplier: 5.0,
+            army_multiplier: 1.0,
+            army_same_territory: 10.0,
+            army_bordering: 25.0,
+            minimum_armies: 3,
+            attack_delta_multiplier: 50.0,
+            acropolis_value: 10.0,
+            agropolis_value: 15.0,
+            metropolis_value: 40.0,
+            fortification_value: 8.0,
+            territory_value: 60.0,
+        };
+        match personality {
+            Personality::Balanced => {}
+
+            // Attacks early and often, armies are there to be spent
+            Personality::Aggressor => {
+                profile.army_bordering = 12.0;
+                profile.minimum_armies = 2;
+                profile.attack_delta_multiplier = 100.0;
+            }
+
+            // Sits behind walls and only strikes with overwhelming numbers
+            Personality::Turtle => {
+                profile.army_bordering = 40.0;
+                profile.minimum_armies = 5;
+                profile.attack_delta_multiplier = 20.0;
+                profile.fortification_value = 20.0;
+            }
+
+            // Wants whole territories and holds them
+            Personality::TerritoryHoarder => {
+                profile.army_same_territory = 20.0;
+                profile.territory_value = 150.0;
+            }
+
+            // Goes for the richest city going, wherever it is
+            Personality::Opportunist => {
+                profile.attack_delta_multiplier = 75.0;
+                profile.metropolis_value = 60.0;
+                profile.acropolis_value = 15.0;
+                profile.territory_value = 20.0;
+            }
+        }
+        profile
+    }
 }
@@ -0,0 +1,79 @@
+use crate::model::world_fixed::WorldFixed;
+use skia_safe::{Path, PathOp, Point, Rect};
+
+// Level of detail the land is cut at, close enough to the coast at any zoom while keeping the cutting quick
+const REGION_DETAIL: usize = 2;
+
+// How far past a territory the outermost cells reach before its land trims them
+const MARGIN: f32 = 10.0;
+
+// The part of a convex polygon nearer `site` than `other`, cut along the line halfway between them
+fn clip_half_plane(polygon: &[Point], site: Point, other: Point) -> Vec<Point> {
+    let normal = other - site;
+    let middle = site + normal * 0.5;
+    let side = |point: Point| (point.x - middle.x) * normal.x + (point.y - middle.y) * normal.y;
+
+    let mut clipped = Vec::with_capacity(polygon.len() + 1);
+    for (index, point) in polygon.iter().enumerate() {
+        let next = polygon[(index + 1) % polygon.len()];
+        let (here, there) = (side(*point), side(next));
+        if here <= 0.0 {
+            clipped.push(*point);
+        }
+        if (here < 0.0 && there > 0.0) || (here > 0.0 && there < 0.0) {
+            clipped.push(*point + (next - *point) * (here / (here - there)));
+        }
+    }
+    clipped
+}
+
+// Everywhere nearer the city at `index` than any other, within `bounds`
+pub fn voronoi_cell(sites: &[Point], index: usize, bounds: Rect) -> Vec<Point> {
+    let mut cell = vec![
+        Point::new(bounds.left, bounds.top),
+        Point::new(bounds.right, bounds.top),
+        Point::new(bounds.right, bounds.bottom),
+        Point::new(bounds.left, bounds.bottom),
+    ];
+    for (other_index, other) in sites.iter().enumerate() {
+        if other_index != index && cell.len() >= 3 {
+            cell = clip_half_plane(&cell, sites[index], *other);
+        }
+    }
+    cell
+}
+
+// Each city's share of its territory, the part nearer it than any of the territory's other cities
+pub fn build_regions(world_fixed: &WorldFixed) -> Vec<Path> {
+    let mut regions = vec![Path::new(); world_fixed.cities.len()];
+    for territory in world_fixed.territories.values() {
+        if territory.cities.is_empty() {
+            continue;
+        }
+        let sites: Vec<Point> =
+            territory.cities.iter().map(|city_index| world_fixed.cities[*city_index].location.p).collect();
+        let mut land = Path::new();
+        let mut bounds = Rect::new_empty();
+        for polygon in &territory.polygons {
+            land.add_path(&polygon.paths[REGION_DETAIL], (0.0, 0.0), None);
+            bounds.join(polygon.bounds);
+        }
+        let bounds = bounds.with_outset((MARGIN, MARGIN));
+
+        // A lone city has the lot
+        if sites.len() == 1 {
+            regions[territory.cities[0]] = land;
+            continue;
+        }
+        for (site_index, city_index) in territory.cities.iter().enumerate() {
+            let cell = voronoi_cell(&sites, site_index, bounds);
+            if cell.len() < 3 {
+                continue;
+            }
+            let mut cell_path = Path::new();
+            cell_path.add_poly(&cell, true);
+            regions[*city_index] = cell_path.op(&land, PathOp::Intersect).unwrap_or_default();
+        }
+    }
+    regions
+}
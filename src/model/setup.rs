@@ -0,0 +1,285 @@
+use crate::lib::cbor::{CITIES_PER_TERRITORY, MAPS};
+use crate::model::profile::{Difficulty, Personality, DIFFICULTIES, PERSONALITIES};
+use rand::prelude::SliceRandom;
+use rand::{rng, Rng};
+use skia_safe::Color;
+
+pub const MINIMUM_PLAYERS: usize = 2;
+pub const MAXIMUM_PLAYERS: usize = 5;
+pub const MINIMUM_ALLOWED_DISTANCE: f32 = 18.0;
+pub const STARTING_ARMIES: u32 = 10;
+
+// Steps each option goes through when clicked, wrapping round
+const CITIES_PER_TERRITORY_CHOICES: [usize; 6] = [10, 15, 20, 25, 30, 40];
+const MINIMUM_ALLOWED_DISTANCE_CHOICES: [f32; 5] = [12.0, 15.0, 18.0, 21.0, 24.0];
+const STARTING_ARMIES_CHOICES: [u32; 6] = [5, 10, 15, 20, 25, 30];
+const RANDOM_SHARE_CHOICES: [usize; 3] = [25, 50, 75];
+
+pub const FACTION_NAMES: [&str; 20] = [
+    "The Britannian Dominion",
+    "The Red Tsardom",
+    "The Iron Kaisers",
+    "The Rising Shogunate",
+    "The Gaulish Syndicate",
+    "The Yankee Federation",
+    "The Ottoman Remnants",
+    "The Austro Imperium",
+    "The Persian Ascendants",
+    "The Italian Legions",
+    "The Dragon Empire",
+    "The Iberian Dominion",
+    "The Nordic Coalition",
+    "The Balkan Confederacy",
+    "The Egyptian Dynasts",
+    "The Prussian Order",
+    "The Celtic Union",
+    "The Maharaja Confederation",
+    "The Andean Empire",
+    "The Hellenic Guardians",
+];
+
+// Fill and text colour for each player
+pub const PLAYER_COLOURS: [[Color; 2]; 8] = [
+    [Color::from_rgb(128, 128, 255), Color::BLACK],
+    [Color::from_rgb(255, 128, 128), Color::BLACK],
+    [Color::from_rgb(128, 255, 128), Color::BLACK],
+    [Color::from_rgb(255, 255, 128), Color::BLACK],
+    [Color::from_rgb(128, 255, 255), Color::BLACK],
+    [Color::from_rgb(255, 128, 255), Color::BLACK],
+    [Color::from_rgb(255, 192, 128), Color::BLACK],
+    [Color::from_rgb(192, 128, 255), Color::BLACK],
+];
+
+// How the cities are shared out before the armies go down
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum CityAssignment {
+    #[default]
+    Random,
+    Draft,
+    Hybrid,
+}
+
+pub const CITY_ASSIGNMENTS: [CityAssignment; 3] =
+    [CityAssignment::Random, CityAssignment::Draft, CityAssignment::Hybrid];
+
+impl CityAssignment {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CityAssignment::Random => "Random",
+            CityAssignment::Draft => "Draft",
+            CityAssignment::Hybrid => "Random then draft",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetupField {
+    Players,
+    Map,
+    CitiesPerTerritory,
+    MinimumAllowedDistance,
+    StartingArmies,
+    Assignment,
+    RandomShare,
+    FogOfWar,
+    Seed,
+    Colour(usize),
+    Name(usize),
+    Human(usize),
+    Difficulty(usize),
+    Personality(usize),
+}
+
+// Options shown under the player list, in order
+pub const SETUP_OPTIONS: [SetupField; 9] = [
+    SetupField::Players,
+    SetupField::Map,
+    SetupField::CitiesPerTerritory,
+    SetupField::MinimumAllowedDistance,
+    SetupField::StartingArmies,
+    SetupField::Assignment,
+    SetupField::RandomShare,
+    SetupField::FogOfWar,
+    SetupField::Seed,
+];
+
+#[derive(Debug, Clone)]
+pub struct PlayerSetup {
+    pub name: usize,
+    pub colour: usize,
+    pub human: bool,
+    pub difficulty: Difficulty,
+    pub personality: Personality,
+}
+
+// Everything chosen on the setup screen before the world is built
+#[derive(Debug, Clone)]
+pub struct GameSetup {
+    pub players: Vec<PlayerSetup>,
+    pub map: usize,
+    pub cities_per_territory: usize,
+    pub minimum_allowed_distance: f32,
+    pub starting_armies: u32,
+    pub assignment: CityAssignment,
+    pub random_share: usize,
+    // Optional rules
+    pub fog_of_war: bool,
+    pub seed: u64,
+}
+
+// The next entry after the current one that nobody else has, or the current one if they're all taken
+fn next_free(current: usize, count: usize, taken: &[usize]) -> usize {
+    (1..count).map(|step| (current + step) % count).find(|index| !taken.contains(index)).unwrap_or(current)
+}
+
+// Next choice along, back to the start after the last, or the first if the value isn't one of them
+fn next_choice<T: Copy + PartialEq>(choices: &[T], current: T) -> T {
+    let position = choices.iter().position(|choice| *choice == current);
+    choices[position.map_or(0, |position| (position + 1) % choices.len())]
+}
+
+impl GameSetup {
+    // Computer players throughout with their own personalities, names drawn at random and a fresh seed
+    pub fn new(num_of_players: usize, difficulty: Difficulty) -> Self {
+        let mut rng = rng();
+        let mut names: Vec<usize> = (0..FACTION_NAMES.len()).collect();
+        names.shuffle(&mut rng);
+        let players = (0..num_of_players)
+            .map(|index| PlayerSetup {
+                name: names[index],
+                colour: index,
+                human: false,
+                difficulty,
+                personality: PERSONALITIES[index % PERSONALITIES.len()],
+            })
+            .collect();
+        Self {
+            players,
+            map: 0,
+            cities_per_territory: CITIES_PER_TERRITORY,
+            minimum_allowed_distance: MINIMUM_ALLOWED_DISTANCE,
+            starting_armies: STARTING_ARMIES,
+            assignment: CityAssignment::default(),
+            random_share: 50,
+            fog_of_war: true,
+            seed: rng.random(),
+        }
+    }
+
+    // How many of the cities are left for the players to pick once the rest have been handed out
+    pub fn cities_to_draft(&self, cities: usize) -> usize {
+        match self.assignment {
+            CityAssignment::Random => 0,
+            CityAssignment::Draft => cities,
+            CityAssignment::Hybrid => cities - cities * self.random_share / 100,
+        }
+    }
+
+    // What can be clicked on, a computer player's difficulty and personality don't apply to humans, and
+    // the random share only to a mix of the two
+    pub fn fields(&self) -> Vec<SetupField> {
+        let mut fields = SETUP_OPTIONS.to_vec();
+        if self.assignment != CityAssignment::Hybrid {
+            fields.retain(|field| *field != SetupField::RandomShare);
+        }
+        for (index, player) in self.players.iter().enumerate() {
+            fields.extend([SetupField::Colour(index), SetupField::Name(index), SetupField::Human(index)]);
+            if !player.human {
+                fields.extend([SetupField::Difficulty(index), SetupField::Personality(index)]);
+            }
+        }
+        fields
+    }
+
+    pub fn value(&self, field: SetupField) -> String {
+        match field {
+            SetupField::Players => self.players.len().to_string(),
+            SetupField::Map => MAPS[self.map].0.to_string(),
+            SetupField::CitiesPerTerritory => self.cities_per_territory.to_string(),
+            SetupField::MinimumAllowedDistance => format!("{:.0}", self.minimum_allowed_distance),
+            SetupField::StartingArmies => self.starting_armies.to_string(),
+            SetupField::Assignment => self.assignment.name().to_string(),
+            SetupField::RandomShare => format!("{}%", self.random_share),
+            SetupField::FogOfWar if self.fog_of_war => "On".to_string(),
+            SetupField::FogOfWar => "Off".to_string(),
+            SetupField::Seed => self.seed.to_string(),
+            SetupField::Colour(_) => String::new(),
+            SetupField::Name(index) => FACTION_NAMES[self.players[index].name].to_string(),
+            SetupField::Human(index) if self.players[index].human => "Human".to_string(),
+            SetupField::Human(_) => "Computer".to_string(),
+            SetupField::Difficulty(index) => self.players[index].difficulty.name().to_string(),
+            SetupField::Personality(index) => self.players[index].personality.name().to_string(),
+        }
+    }
+
+    pub fn label(field: SetupField) -> &'static str {
+        match field {
+            SetupField::Players => "Players",
+            SetupField::Map => "Map",
+            SetupField::CitiesPerTerritory => "Cities per territory",
+            SetupField::MinimumAllowedDistance => "City spacing",
+            SetupField::StartingArmies => "Starting armies",
+            SetupField::Assignment => "Cities handed out",
+            SetupField::RandomShare => "Share at random",
+            SetupField::FogOfWar => "Fog of war",
+            SetupField::Seed => "Seed",
+            SetupField::Colour(_) => "Colour",
+            SetupField::Name(_) => "Faction",
+            SetupField::Human(_) => "Controller",
+            SetupField::Difficulty(_) => "Difficulty",
+            SetupField::Personality(_) => "Personality",
+        }
+    }
+
+    // Clicking an option moves it on to its next value
+    pub fn step(&mut self, field: SetupField) {
+        match field {
+            SetupField::Players => {
+                if self.players.len() == MAXIMUM_PLAYERS {
+                    self.players.truncate(MINIMUM_PLAYERS);
+                } else {
+                    let index = self.players.len();
+                    let names: Vec<usize> = self.players.iter().map(|player| player.name).collect();
+                    let colours: Vec<usize> = self.players.iter().map(|player| player.colour).collect();
+                    self.players.push(PlayerSetup {
+                        name: next_free(index, FACTION_NAMES.len(), &names),
+                        colour: next_free(index, PLAYER_COLOURS.len(), &colours),
+                        human: false,
+                        difficulty: self.players.last().map(|player| player.difficulty).unwrap_or_default(),
+                        personality: PERSONALITIES[index % PERSONALITIES.len()],
+                    });
+                }
+            }
+            SetupField::Map => self.map = (self.map + 1) % MAPS.len(),
+            SetupField::CitiesPerTerritory => {
+                self.cities_per_territory = next_choice(&CITIES_PER_TERRITORY_CHOICES, self.cities_per_territory)
+            }
+            SetupField::MinimumAllowedDistance => {
+                self.minimum_allowed_distance =
+                    next_choice(&MINIMUM_ALLOWED_DISTANCE_CHOICES, self.minimum_allowed_distance)
+            }
+            SetupField::StartingArmies => {
+                self.starting_armies = next_choice(&STARTING_ARMIES_CHOICES, self.starting_armies)
+            }
+            SetupField::Assignment => self.assignment = next_choice(&CITY_ASSIGNMENTS, self.assignment),
+            SetupField::RandomShare => self.random_share = next_choice(&RANDOM_SHARE_CHOICES, self.random_share),
+            SetupField::FogOfWar => self.fog_of_war = !self.fog_of_war,
+            SetupField::Seed => self.seed = rng().random(),
+            SetupField::Colour(index) => {
+                let taken: Vec<usize> = self.players.iter().map(|player| player.colour).collect();
+                self.players[index].colour = next_free(self.players[index].colour, PLAYER_COLOURS.len(), &taken);
+            }
+            SetupField::Name(index) => {
+                let taken: Vec<usize> = self.players.iter().map(|player| player.name).collect();
+                self.players[index].name = next_free(self.players[index].name, FACTION_NAMES.len(), &taken);
+            }
+            SetupField::Human(index) => self.players[index].human = !self.players[index].human,
+            SetupField::Difficulty(index) => {
+                self.players[index].difficulty = next_choice(&DIFFICULTIES, self.players[index].difficulty)
+            }
+            SetupField::Personality(index) => {
+                self.players[index].personality = next_choice(&PERSONALITIES, self.players[index].personality)
+            }
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use skia_safe::{Point, Rect};
+
+// How far a simplified outline may stray from the original at each level of detail, in map units. The first is
+// everything, the rest are picked as the view zooms out
+pub const LEVELS_OF_DETAIL: [f32; 5] = [0.0, 0.025, 0.05, 0.1, 0.2];
+
+// Islands are kept at least this many times the level's tolerance across, a few pixels when it's in use
+const MINIMUM_ISLAND: f32 = 6.0;
+
+// The coarsest level that strays no more than half a pixel at this zoom
+pub fn level_for_scale(scale: f32) -> usize {
+    let tolerance = 0.5 / scale;
+    LEVELS_OF_DETAIL.iter().rposition(|level| *level <= tolerance).unwrap_or(0)
+}
+
+// Distance from a point to the segment between two others
+fn segment_distance(point: Point, start: Point, end: Point) -> f32 {
+    let line = end - start;
+    let length_squared = line.x * line.x + line.y * line.y;
+    if length_squared == 0.0 {
+        return (point - start).length();
+    }
+    let along = (((point.x - start.x) * line.x + (point.y - start.y) * line.y) / length_squared).clamp(0.0, 1.0);
+    (point - (start + line * along)).length()
+}
+
+// Douglas-Peucker on an open run of points, marking those to keep
+fn simplify_line(points: &[Point], tolerance: f32, keep: &mut [bool]) {
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((start, end)) = stack.pop() {
+        let mut furthest = (start, 0.0);
+        for (index, point) in points.iter().enumerate().take(end).skip(start + 1) {
+            let distance = segment_distance(*point, points[start], points[end]);
+            if distance > furthest.1 {
+                furthest = (index, distance);
+            }
+        }
+        if furthest.1 > tolerance {
+            keep[furthest.0] = true;
+            stack.push((start, furthest.0));
+            stack.push((furthest.0, end));
+        }
+    }
+}
+
+// A closed outline with fewer points, split in two at the point furthest from the first so each half is a
+// line. Never less than a triangle
+pub fn simplify_ring(points: &[Point], tolerance: f32) -> Vec<Point> {
+    if tolerance <= 0.0 || points.len() <= 4 {
+        return points.to_vec();
+    }
+    let count = points.len();
+    let mut ring = points.to_vec();
+    ring.push(points[0]);
+    let far = (1..count)
+        .max_by(|a, b| (points[*a] - points[0]).length().total_cmp(&(points[*b] - points[0]).length()))
+        .unwrap();
+
+    let mut keep = vec![false; count + 1];
+    keep[0] = true;
+    keep[far] = true;
+    simplify_line(&ring[..=far], tolerance, &mut keep[..=far]);
+    simplify_line(&ring[far..], tolerance, &mut keep[far..]);
+
+    let simplified: Vec<Point> = (0..count).filter(|index| keep[*index]).map(|index| points[index]).collect();
+    if simplified.len() >= 3 {
+        simplified
+    } else {
+        vec![points[0], points[count / 3], points[count * 2 / 3]]
+    }
+}
+
+// Grow an outline about its middle until it's big enough to see at this level
+pub fn keep_visible(points: &mut [Point], tolerance: f32) {
+    let minimum = tolerance * MINIMUM_ISLAND;
+    let mut bounds = Rect::new(points[0].x, points[0].y, points[0].x, points[0].y);
+    for point in points.iter() {
+        bounds.left = bounds.left.min(point.x);
+        bounds.top = bounds.top.min(point.y);
+        bounds.right = bounds.right.max(point.x);
+        bounds.bottom = bounds.bottom.max(point.y);
+    }
+    let size = bounds.width().max(bounds.height());
+    if minimum <= 0.0 || size >= minimum {
+        return;
+    }
+    let centre = bounds.center();
+    let scale = if size > 0.0 { minimum / size } else { 1.0 };
+    for point in points.iter_mut() {
+        *point = centre + (*point - centre) * scale;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A wobbly circle, so every level has something to take out
+    fn outline(points: usize, radius: f32) -> Vec<Point> {
+        (0..points)
+            .map(|index| {
+                let angle = index as f32 / points as f32 * std::f32::consts::TAU;
+                let wobble = if index % 2 == 0 { 1.0 } else { 0.999 };
+                Point::new(angle.cos() * radius * wobble, angle.sin() * radius * wobble)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn simplified_rings_keep_their_endpoints() {
+        let points = outline(200, 10.0);
+        let far = points.len() / 2;
+        for tolerance in LEVELS_OF_DETAIL.iter().skip(1) {
+            let simplified = simplify_ring(&points, *tolerance);
+            assert!(simplified.len() >= 3 && simplified.len() < points.len());
+            assert_eq!(simplified[0], points[0]);
+            assert!(simplified.contains(&points[far]));
+            assert!(simplified.iter().all(|point| points.contains(point)));
+        }
+
+        // Everything at full detail, and nothing less than a triangle
+        assert_eq!(simplify_ring(&points, 0.0), points);
+        assert!(simplify_ring(&outline(20, 0.001), 1.0).len() >= 3);
+    }
+
+    #[test]
+    fn small_islands_grow_to_be_seen() {
+        let tolerance = LEVELS_OF_DETAIL[4];
+        let mut island = outline(12, 0.01);
+        keep_visible(&mut island, tolerance);
+        let width = island.iter().map(|point| point.x).fold(f32::MIN, f32::max)
+            - island.iter().map(|point| point.x).fold(f32::MAX, f32::min);
+        assert!(width >= tolerance * MINIMUM_ISLAND * 0.99);
+        assert!(island[0].x > 0.0 && island[island.len() / 2].x < 0.0);
+
+        // Anything big enough already is left alone
+        let mainland = outline(12, 10.0);
+        let mut kept = mainland.clone();
+        keep_visible(&mut kept, tolerance);
+        assert_eq!(kept, mainland);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::model::city::SIZE;
+use crate::model::territory::TerritoryArc;
+use crate::model::world_fixed::WorldFixed;
+use skia_safe::{Point, Rect};
+
+// Map units along each side of a grid cell, a handful of cities across
+const CELL_SIZE: f32 = 10.0;
+
+// Edges touching counts, so points and lines with no area can still be found
+fn overlaps(a: &Rect, b: &Rect) -> bool {
+    a.left <= b.right && b.left <= a.right && a.top <= b.bottom && b.top <= a.bottom
+}
+
+// Items filed under every cell their bounds cover, so looking in an area only checks what's nearby
+#[derive(Debug, Default)]
+pub struct Grid {
+    origin: Point,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+    bounds: Vec<Rect>,
+}
+
+impl Grid {
+    pub fn new(bounds: Vec<Rect>) -> Self {
+        if bounds.is_empty() {
+            return Grid::default();
+        }
+        let left = bounds.iter().map(|rect| rect.left).fold(f32::MAX, f32::min);
+        let top = bounds.iter().map(|rect| rect.top).fold(f32::MAX, f32::min);
+        let right = bounds.iter().map(|rect| rect.right).fold(f32::MIN, f32::max);
+        let bottom = bounds.iter().map(|rect| rect.bottom).fold(f32::MIN, f32::max);
+        let mut grid = Grid {
+            origin: Point::new(left, top),
+            columns: ((right - left) / CELL_SIZE) as usize + 1,
+            rows: ((bottom - top) / CELL_SIZE) as usize + 1,
+            cells: Vec::new(),
+            bounds: Vec::new(),
+        };
+        grid.cells = vec![Vec::new(); grid.columns * grid.rows];
+        for (index, rect) in bounds.iter().enumerate() {
+            let (columns, rows) = grid.cell_range(rect);
+            for row in rows {
+                for column in columns.clone() {
+                    grid.cells[row * grid.columns + column].push(index);
+                }
+            }
+        }
+        grid.bounds = bounds;
+        grid
+    }
+
+    // Cells covered by an area, clamped to the grid
+    fn cell_range(&self, rect: &Rect) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let cell = |value: f32, origin: f32, count: usize| {
+            (((value - origin) / CELL_SIZE).floor().max(0.0) as usize).min(count.saturating_sub(1))
+        };
+        let columns = cell(rect.left, self.origin.x, self.columns)..cell(rect.right, self.origin.x, self.columns) + 1;
+        let rows = cell(rect.top, self.origin.y, self.rows)..cell(rect.bottom, self.origin.y, self.rows) + 1;
+        (columns, rows)
+    }
+
+    // Everything whose bounds meet the area, in the order they were given
+    pub fn query(&self, rect: Rect) -> Vec<usize> {
+        if self.cells.is_empty() {
+            return Vec::new();
+        }
+        let (columns, rows) = self.cell_range(&rect);
+        let mut found = Vec::new();
+        for row in rows {
+            for column in columns.clone() {
+                found.extend(
+                    self.cells[row * self.columns + column]
+                        .iter()
+                        .filter(|index| overlaps(&self.bounds[**index], &rect)),
+                );
+            }
+        }
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+}
+
+// Where the cities, territory outlines and connection lines are, for picking with the mouse and skipping
+// whatever's off screen
+#[derive(Debug, Default)]
+pub struct SpatialIndex {
+    pub cities: Grid,
+    pub polygons: Grid,
+    // Each polygon's territory and its place in that territory's list
+    pub polygon_keys: Vec<(TerritoryArc, usize)>,
+    pub connections: Grid,
+}
+
+impl SpatialIndex {
+    pub fn new(world_fixed: &WorldFixed) -> Self {
+        let cities = world_fixed
+            .cities
+            .iter()
+            .map(|city| Rect::from_xywh(city.location.p.x - SIZE, city.location.p.y - SIZE, SIZE * 2.0, SIZE * 2.0))
+            .collect();
+
+        let mut polygon_keys = Vec::new();
+        let mut polygons = Vec::new();
+        for territory in world_fixed.territories.values() {
+            for (polygon_index, polygon) in territory.polygons.iter().enumerate() {
+                polygon_keys.push((territory.clone(), polygon_index));
+                polygons.push(polygon.bounds);
+            }
+        }
+
+        let connections = world_fixed
+            .connections
+            .iter()
+            .map(|connection| {
+                let from = world_fixed.cities[connection.city1].location.p;
+                let to = world_fixed.cities[connection.city2].location.p;
+                Rect::new(from.x.min(to.x), from.y.min(to.y), from.x.max(to.x), from.y.max(to.y))
+            })
+            .collect();
+
+        SpatialIndex {
+            cities: Grid::new(cities),
+            polygons: Grid::new(polygons),
+            polygon_keys,
+            connections: Grid::new(connections),
+        }
+    }
+
+    // Cities within `radius` of a point, nearest first
+    pub fn cities_near(&self, world_fixed: &WorldFixed, point: Point, radius: f32) -> Vec<usize> {
+        let area = Rect::from_xywh(point.x - radius, point.y - radius, radius * 2.0, radius * 2.0);
+        let mut near: Vec<(usize, f32)> = self
+            .cities
+            .query(area)
+            .into_iter()
+            .map(|city_index| (city_index, (world_fixed.cities[city_index].location.p - point).length()))
+            .filter(|(_, distance)| *distance <= radius)
+            .collect();
+        near.sort_by(|a, b| a.1.total_cmp(&b.1));
+        near.into_iter().map(|(city_index, _)| city_index).collect()
+    }
+}
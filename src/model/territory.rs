@@ -1,4 +1,4 @@
-use crate::model::city::CityRR;
+use crate::model::city::CityStatic;
 use crate::model::territory_polygon::TerritoryPolygon;
 use crate::model::world_fixed::WorldFixed;
 use skia_safe::Color;
@@ -20,7 +20,8 @@ pub fn get_colour_for_territory_name(name: &String) -> Color {
 
 #[derive(Debug, Default)]
 pub struct Territory {
-    pub cities: Vec<CityRR>,
+    pub cities: Vec<usize>,
+    pub candidates: Vec<CityStatic>,
     pub polygons: Vec<TerritoryPolygon>,
     pub name: String,
     pub colour: Color,
@@ -33,7 +34,7 @@ impl Territory {
         let container = Arc::new(self);
 
         // Update cities
-        container.cities.iter().for_each(|city| city.borrow().statics.borrow_mut().territory_name =
+        container.cities.iter().for_each(|city| world_fixed.cities[*city].territory_name =
             container.name.clone());
 
         container
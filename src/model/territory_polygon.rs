@@ -1,37 +1,56 @@
-use crate::lib::skia::Skia;
 use crate::model::location::Location;
-use skia_safe::{Color, Paint, PaintStyle, Path, Picture, PictureRecorder, Rect};
+use crate::model::simplify::{keep_visible, simplify_ring, LEVELS_OF_DETAIL};
+use skia_safe::{Color, Paint, PaintStyle, Path, Picture, PictureRecorder, Point, Rect};
 
 #[derive(Debug)]
 pub struct TerritoryPolygon {
-    pub pic: Picture,
+    // One outline and picture of it for each level of detail, finest first, and how many points went into it
+    pub paths: Vec<Path>,
+    pub pics: Vec<Picture>,
+    pub points: Vec<usize>,
+    pub bounds: Rect,
 }
 
 impl TerritoryPolygon {
-    pub fn new(_skia: &mut Skia, colour: Color, locations: Vec<Location>) -> Self {
+    pub fn new(colour: Color, locations: Vec<Location>) -> Self {
         // Paint
         let mut paint = Paint::default();
         paint.set_style(PaintStyle::Fill);
         paint.set_argb(255, 255, 0, 0);
         paint.set_color(colour);
 
-        // Construct path
-        let mut path = Path::new();
-        path.move_to(locations[0].p);
-        for location in locations.iter().skip(1) {
-            path.line_to(location.p);
-        }
-        path.close();
+        let outline: Vec<Point> = locations.iter().map(|location| location.p).collect();
+        let mut paths = Vec::new();
+        let mut pics = Vec::new();
+        let mut points = Vec::new();
+        let mut bounds = Rect::new_empty();
+        for tolerance in LEVELS_OF_DETAIL {
+            let mut simplified = simplify_ring(&outline, tolerance);
+            keep_visible(&mut simplified, tolerance);
 
-        // And draw to a Picture
-        let mut recorder = PictureRecorder::new();
-        let canvas = recorder.begin_recording(Rect::from_wh(0.0, 0.0), None);
-        //paint.set_shader(skia.halftone(territories.get(territory).unwrap().colour));
-        canvas.draw_path(&path, &paint);
-        let pic = recorder.finish_recording_as_picture(None).unwrap();
+            // Construct path
+            let mut path = Path::new();
+            path.move_to(simplified[0]);
+            for point in simplified.iter().skip(1) {
+                path.line_to(*point);
+            }
+            path.close();
+            bounds.join(path.bounds());
 
-        Self {
-            pic,
+            // And draw to a Picture
+            let mut recorder = PictureRecorder::new();
+            let canvas = recorder.begin_recording(Rect::from_wh(0.0, 0.0), None);
+            //paint.set_shader(skia.halftone(territories.get(territory).unwrap().colour));
+            canvas.draw_path(&path, &paint);
+            pics.push(recorder.finish_recording_as_picture(None).unwrap());
+            points.push(simplified.len());
+            paths.push(path);
         }
+
+        Self { paths, pics, points, bounds }
+    }
+
+    pub fn pic(&self, level: usize) -> &Picture {
+        &self.pics[level]
     }
 }
@@ -0,0 +1,53 @@
+use crate::app_state::{create_players, GameMode};
+use crate::model::city::{CityState, CityStatic};
+use crate::model::connection::Connection;
+use crate::model::profile::Difficulty;
+use crate::model::setup::GameSetup;
+use crate::model::territory::Territory;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::WorldState;
+use std::sync::Arc;
+
+pub const SEED: u64 = 1234;
+
+// Cities in a row, each joined to the next
+pub const CITIES: usize = 8;
+
+// Two territories, the west half of the row held by the first player and the east by the second, on the first
+// player's go
+pub fn line_world() -> (WorldState, WorldFixed) {
+    let mut world_state = WorldState::default();
+    world_state.reseed(SEED);
+    let mut world_fixed = WorldFixed::default();
+    create_players(&mut world_state, &mut world_fixed, &GameSetup::new(2, Difficulty::Normal));
+
+    let territory_name = |city_index: usize| if city_index < CITIES / 2 { "West" } else { "East" };
+    for city_index in 0..CITIES {
+        let name = format!("City {}", city_index);
+        let mut statics = CityStatic::new(name, city_index as f32, 0.0, 200000, territory_name(city_index).to_string());
+        statics.index = city_index;
+        world_fixed.cities.push(statics);
+    }
+    for city_index in 1..CITIES {
+        for (city1, city2) in [(city_index - 1, city_index), (city_index, city_index - 1)] {
+            let same_territory = territory_name(city1) == territory_name(city2);
+            let connection = Arc::new(Connection { city1, city2, render: true, same_territory });
+            world_fixed.connections.push(connection.clone());
+            world_fixed.cities[city1].connections.push(connection);
+        }
+    }
+    for (name, cities) in [("West", 0..CITIES / 2), ("East", CITIES / 2..CITIES)] {
+        let territory = Territory { cities: cities.collect(), name: name.to_string(), ..Territory::default() };
+        world_fixed.territories.insert(name.to_string(), Arc::new(territory));
+    }
+
+    for (city_index, statics) in world_fixed.cities.iter().enumerate() {
+        let mut city = CityState::new(statics);
+        city.owner = Some(if city_index < CITIES / 2 { 0 } else { 1 });
+        city.armies = 2 + city_index % 3;
+        world_state.cities.push(city);
+    }
+    world_state.mode = GameMode::Game;
+    world_state.update_scores(&world_fixed);
+    (world_state, world_fixed)
+}
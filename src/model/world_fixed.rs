@@ -1,13 +1,33 @@
-use crate::model::city::{CityRR};
+use crate::model::city::CityStatic;
 use crate::model::connection::ConnectionArc;
 use crate::model::location::Location;
+use crate::model::player::PlayerStatic;
+use crate::model::spatial_index::SpatialIndex;
 use crate::model::territory::TerritoryArc;
+use skia_safe::{Path, Rect};
 use std::collections::BTreeMap;
 
 #[derive(Debug, Default)]
 pub struct WorldFixed {
     pub territories: BTreeMap<String, TerritoryArc>,
+    pub cities: Vec<CityStatic>,
+    pub players: Vec<PlayerStatic>,
     pub city_locations: Vec<Location>,
     pub connections: Vec<ConnectionArc>,
-    pub cities_to_assign: Vec<CityRR>,
+    pub index: SpatialIndex,
+    // Each city's share of its territory, by city
+    pub regions: Vec<Path>,
+}
+
+impl WorldFixed {
+    // All the land there is, to keep the view over
+    pub fn map_bounds(&self) -> Rect {
+        let mut bounds = Rect::new_empty();
+        for territory in self.territories.values() {
+            for polygon in &territory.polygons {
+                bounds.join(polygon.bounds);
+            }
+        }
+        bounds
+    }
 }
@@ -1,54 +1,370 @@
+use crate::ai::army_placement::MAXIMUM_ARMIES_PER_CITY;
 use crate::app_state::GameMode;
-use crate::model::city::{City, CityRR};
-use crate::model::player::PlayerRR;
+use crate::model::card::{trade_in_bonus, Card, CITY_BONUS_ARMIES};
+use crate::model::city::CityState;
+use crate::model::diplomacy::Diplomacy;
+use crate::model::player::Player;
 use crate::model::world_fixed::WorldFixed;
-use std::cell::RefCell;
-use std::rc::Rc;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-#[derive(Debug, Default, Clone)]
+// Guess at the garrison of a city we can't see into
+const UNSEEN_ARMIES_PER_SIZE: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visibility {
+    Full,
+    Owner,
+    Hidden,
+}
+
+// Everything that changes during a game, flat and index based so copies are cheap and can cross threads
+#[derive(Debug, Clone)]
 pub struct WorldState {
     pub mode: GameMode,
-    pub current_player: Option<PlayerRR>,
-    pub players: Vec<PlayerRR>,
-    pub cities: Vec<CityRR>,
+    pub current_player: usize,
+    pub players: Vec<Player>,
+    pub cities: Vec<CityState>,
+    pub city_scores: Vec<f32>,
+    pub cities_to_assign: Vec<usize>,
+    pub cities_to_draft: usize,
+    pub deck: Vec<Card>,
+    pub sets_traded: usize,
+    pub fog_of_war: bool,
+    pub diplomacy: Diplomacy,
+    // Bumped whenever a city changes hands, so what's drawn from who owns what knows to be redone
+    pub ownership_changes: u32,
+    pub rng: StdRng,
+}
+
+impl Default for WorldState {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            current_player: 0,
+            players: Vec::new(),
+            cities: Vec::new(),
+            city_scores: Vec::new(),
+            cities_to_assign: Vec::new(),
+            cities_to_draft: 0,
+            deck: Vec::new(),
+            sets_traded: 0,
+            fog_of_war: false,
+            diplomacy: Diplomacy::default(),
+            ownership_changes: 0,
+            rng: StdRng::from_os_rng(),
+        }
+    }
 }
 
 impl WorldState {
-    pub fn deep_clone(&self) -> Self {
-        let mut players = Vec::new();
-        for player in &self.players {
-            players.push(Rc::new(RefCell::new(player.borrow().clone())));
+    // Same seed, same shuffles and dice, same game
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    // Own cities and their neighbours in full, owners one connection further out, nothing beyond. The
+    // whole map is open while drafting, there's no picking from what can't be seen
+    pub fn visibility(&self, world_fixed: &WorldFixed, viewer: Option<usize>) -> Vec<Visibility> {
+        let viewer = match viewer {
+            Some(viewer) if self.fog_of_war && self.mode != GameMode::Draft => viewer,
+            _ => return vec![Visibility::Full; self.cities.len()],
+        };
+        let mut visibility = vec![Visibility::Hidden; self.cities.len()];
+        for (city_index, city) in self.cities.iter().enumerate() {
+            if city.owner == Some(viewer) {
+                visibility[city_index] = Visibility::Full;
+                for connection in world_fixed.cities[city_index].connections.iter() {
+                    visibility[connection.city2] = Visibility::Full;
+                }
+            }
         }
-        let mut cities = Vec::new();
-        for city in &self.cities {
-            let new_city = City::full_clone(city);
-            cities.push(new_city);
+        for city_index in 0..self.cities.len() {
+            if visibility[city_index] == Visibility::Full {
+                for connection in world_fixed.cities[city_index].connections.iter() {
+                    if visibility[connection.city2] == Visibility::Hidden {
+                        visibility[connection.city2] = Visibility::Owner;
+                    }
+                }
+            }
         }
-        Self {
-            mode: self.mode.clone(),
-            current_player: Some(players[self.current_player.as_ref().unwrap().borrow().index].clone()),
-            players,
-            cities,
+        visibility
+    }
+
+    // Copy of the world with everything the viewer can't see replaced by guesses, so the AI can't cheat
+    pub fn as_seen_by(&self, world_fixed: &WorldFixed, viewer: usize) -> WorldState {
+        let mut world_state = self.clone();
+
+        // Its own dice, so simulated battles can't foretell the real ones
+        world_state.reseed(self.rng.clone().random());
+        if !self.fog_of_war {
+            return world_state;
+        }
+        let visibility = self.visibility(world_fixed, Some(viewer));
+        for (city_index, city) in world_state.cities.iter_mut().enumerate() {
+            match visibility[city_index] {
+                Visibility::Full => continue,
+                Visibility::Owner => {}
+                Visibility::Hidden => city.owner = None,
+            }
+            city.armies = (city.size as usize * UNSEEN_ARMIES_PER_SIZE).min(MAXIMUM_ARMIES_PER_CITY);
+            city.growth = 0.0;
+            city.besieged = false;
+            city.fortification = 0;
+            city.cavalry = 0;
+        }
+        for player in world_state.players.iter_mut() {
+            if player.index != viewer {
+                player.cards.clear();
+                player.gold = 0;
+            }
+        }
+
+        // Scores as the viewer would reckon them, from the cities they know about
+        world_state.update_scores(world_fixed);
+        world_state
+    }
+
+    // What a city is worth to whoever holds it
+    fn city_score(&self, world_fixed: &WorldFixed, city_index: usize) -> f32 {
+        let city = &self.cities[city_index];
+        match city.owner {
+            Some(owner) => city.score(&world_fixed.cities[city_index], self, &self.players[owner].profile),
+            None => 0.0,
+        }
+    }
+
+    // Totals added up in city order, so they come out the same as `Player::get_score`
+    fn sum_city_scores(&mut self) {
+        let mut totals = vec![0f32; self.players.len()];
+        for (city, score) in self.cities.iter().zip(self.city_scores.iter()) {
+            if let Some(owner) = city.owner {
+                totals[owner] += score;
+            }
+        }
+        for (player, total) in self.players.iter_mut().zip(totals) {
+            player.score = total as i32;
         }
     }
 
     pub fn update_scores(&mut self, world_fixed: &WorldFixed) {
-        for player in self.players.iter() {
-            player.borrow_mut().calculate_score(self, world_fixed);
+        self.city_scores = (0..self.cities.len()).map(|city_index| self.city_score(world_fixed, city_index)).collect();
+        self.sum_city_scores();
+    }
+
+    // Only rescore the cities a move changed and their neighbours, whose border bonuses depend on who
+    // owns them. Relies on the scores being current before the move
+    pub fn update_scores_after(&mut self, world_fixed: &WorldFixed, touched: &[usize]) {
+        if self.city_scores.len() != self.cities.len() {
+            self.update_scores(world_fixed);
+            return;
+        }
+        for &city_index in touched {
+            self.city_scores[city_index] = self.city_score(world_fixed, city_index);
+            for connection in world_fixed.cities[city_index].connections.iter() {
+                self.city_scores[connection.city2] = self.city_score(world_fixed, connection.city2);
+            }
+        }
+        self.sum_city_scores();
+
+        debug_assert!(
+            self.players.iter().all(|player| player.score == player.get_score(self, world_fixed) as i32),
+            "Incremental scores differ from a full recompute"
+        );
+    }
+
+    // The current player has armies waiting and a city with room for them
+    pub fn can_place_army(&self) -> bool {
+        let current_player = self.get_current_player_index();
+        self.get_current_player().armies_to_assign > 0
+            && self
+                .cities
+                .iter()
+                .any(|city| city.owner == Some(current_player) && city.armies < MAXIMUM_ARMIES_PER_CITY)
+    }
+
+    // Allies never need guarding against, a truce partner will once it runs out
+    pub fn borders_enemy(&self, world_fixed: &WorldFixed, city_index: usize) -> bool {
+        let owner = self.cities[city_index].owner;
+        world_fixed.cities[city_index].connections.iter().any(|connection| {
+            match (owner, self.cities[connection.city2].owner) {
+                (Some(owner), Some(other)) => !self.diplomacy.allied(owner, other),
+                (owner, other) => owner != other,
+            }
+        })
+    }
+
+    // Another owner's city, and not one we've a treaty with
+    pub fn can_attack(&self, source: usize, target: usize) -> bool {
+        match (self.cities[source].owner, self.cities[target].owner) {
+            (Some(attacker), Some(defender)) => attacker != defender && !self.diplomacy.at_peace(attacker, defender),
+            (attacker, defender) => attacker != defender,
         }
     }
 
-    pub fn get_current_player(&self) -> PlayerRR {
-        self.current_player.clone().unwrap()
+    // Everyone still holding a city is allied with everyone else, so they win together
+    pub fn shared_victory(&self) -> Option<Vec<usize>> {
+        let mut owners: Vec<usize> = self.cities.iter().filter_map(|city| city.owner).collect();
+        owners.sort();
+        owners.dedup();
+        let all_allied = owners.iter().all(|a| owners.iter().all(|b| self.diplomacy.allied(*a, *b)));
+        (owners.len() > 1 && all_allied).then_some(owners)
+    }
+
+    // Hand a card to the current player if they took a city this turn
+    pub fn award_card(&mut self) {
+        if self.get_current_player().captured_this_turn {
+            if let Some(card) = self.deck.pop() {
+                self.get_current_player_mut().cards.push(card);
+            }
+            self.get_current_player_mut().captured_this_turn = false;
+        }
+    }
+
+    pub fn trade_cards(&mut self, player_index: usize, set: [usize; 3]) {
+        let bonus = trade_in_bonus(self.sets_traded);
+        self.sets_traded += 1;
+        self.players[player_index].armies_to_assign += bonus;
+
+        // Take the cards out of the hand, highest index first so the others stay valid
+        let mut set = set;
+        set.sort_by(|a, b| b.cmp(a));
+        for card_index in set {
+            let card = self.players[player_index].cards.remove(card_index);
+
+            // Extra armies straight into the named city if we hold it
+            if let Some(city_index) = card.city {
+                let city = &mut self.cities[city_index];
+                if city.owner == Some(player_index) {
+                    city.armies = (city.armies + CITY_BONUS_ARMIES).min(MAXIMUM_ARMIES_PER_CITY);
+                }
+            }
+            self.deck.insert(0, card);
+        }
+        debug_log!("Player {} traded in cards for {} armies", player_index, bonus);
+    }
+
+    pub fn develop_cities(&mut self) {
+        for city in self.cities.iter_mut() {
+            if city.owner.is_some() {
+                city.develop();
+            }
+        }
+    }
+
+    pub fn collect_income(&mut self, world_fixed: &WorldFixed) {
+        for (city_index, city) in self.cities.iter().enumerate() {
+            if let Some(owner) = city.owner {
+                self.players[owner].gold += city.income(&world_fixed.cities[city_index]);
+            }
+        }
+    }
+
+    pub fn get_current_player(&self) -> &Player {
+        &self.players[self.current_player]
+    }
+
+    pub fn get_current_player_mut(&mut self) -> &mut Player {
+        &mut self.players[self.current_player]
     }
 
     pub fn get_current_player_index(&self) -> usize {
-        assert!(self.current_player.as_ref().unwrap().borrow().index < self.players.len());
-        self.current_player.as_ref().unwrap().borrow().index
+        assert!(self.current_player < self.players.len());
+        self.current_player
     }
 
-    pub fn get_player_for_index(&self, player: usize) -> PlayerRR {
+    pub fn get_player_for_index(&self, player: usize) -> &Player {
         assert!(player < self.players.len());
-        self.players[player].clone()
+        &self.players[player]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::moves::Move;
+    use crate::model::test_world::line_world;
+
+    #[test]
+    fn fog_shows_neighbours_in_full_and_owners_one_further() {
+        let (mut world_state, world_fixed) = line_world();
+        world_state.fog_of_war = true;
+        let visibility = world_state.visibility(&world_fixed, Some(0));
+        assert_eq!(&visibility[..5], &[Visibility::Full; 5]);
+        assert_eq!(visibility[5], Visibility::Owner);
+        assert_eq!(&visibility[6..], &[Visibility::Hidden; 2]);
+
+        // Everything is open without fog, while drafting or to a spectator
+        world_state.fog_of_war = false;
+        assert!(world_state.visibility(&world_fixed, Some(0)).iter().all(|seen| *seen == Visibility::Full));
+        world_state.fog_of_war = true;
+        assert!(world_state.visibility(&world_fixed, None).iter().all(|seen| *seen == Visibility::Full));
+        world_state.mode = GameMode::Draft;
+        assert!(world_state.visibility(&world_fixed, Some(0)).iter().all(|seen| *seen == Visibility::Full));
+    }
+
+    #[test]
+    fn hidden_cities_lose_their_owner_and_armies() {
+        let (mut world_state, world_fixed) = line_world();
+        world_state.fog_of_war = true;
+        for city in world_state.cities[4..].iter_mut() {
+            city.armies = MAXIMUM_ARMIES_PER_CITY;
+            city.cavalry = 1;
+            city.fortification = 1;
+        }
+        world_state.players[1].gold = 10;
+        world_state.update_scores(&world_fixed);
+        let view = world_state.as_seen_by(&world_fixed, 0);
+
+        // Next door is seen as it is
+        assert_eq!(view.cities[..5], world_state.cities[..5]);
+
+        // Further out only the owner, then nothing at all
+        let guess = |city: &CityState| (city.size as usize * UNSEEN_ARMIES_PER_SIZE).min(MAXIMUM_ARMIES_PER_CITY);
+        assert_eq!(view.cities[5].owner, Some(1));
+        for city in view.cities[6..].iter() {
+            assert_eq!(city.owner, None);
+        }
+        for city in view.cities[5..].iter() {
+            assert_eq!(city.armies, guess(city));
+            assert_eq!((city.cavalry, city.fortification), (0, 0));
+        }
+        assert_eq!(view.players[1].gold, 0);
+
+        // Scored from what's known, not what's there
+        let mut rescored = view.clone();
+        rescored.update_scores(&world_fixed);
+        assert_eq!(view.city_scores, rescored.city_scores);
+        assert!(view.players[1].score < world_state.players[1].score);
+    }
+
+    #[test]
+    fn rescoring_after_moves_matches_scoring_everything() {
+        let (mut world_state, world_fixed) = line_world();
+        world_state.players[0].armies_to_assign = 2;
+        let moves = [
+            Move::new_place_army(3),
+            Move::new_place_army(3),
+            Move::new_attack_city(3, 4),
+            Move::new_attack_city(3, 4),
+            Move::new_attack_city(3, 4),
+        ];
+        for the_move in moves.iter() {
+            the_move.do_move(&mut world_state, &world_fixed);
+            world_state.update_scores_after(&world_fixed, &the_move.touched_cities());
+            let mut rescored = world_state.clone();
+            rescored.update_scores(&world_fixed);
+            assert_eq!(world_state.city_scores, rescored.city_scores);
+            for (player, full) in world_state.players.iter().zip(rescored.players.iter()) {
+                assert_eq!(player.score, full.score);
+            }
+        }
+
+        // A city changing hands also rescores its neighbours' borders
+        world_state.cities[4].owner = Some(0);
+        world_state.update_scores_after(&world_fixed, &[4]);
+        let mut rescored = world_state.clone();
+        rescored.update_scores(&world_fixed);
+        assert_eq!(world_state.city_scores, rescored.city_scores);
     }
 }
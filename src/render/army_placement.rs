@@ -1,4 +1,4 @@
-use crate::app_state::GFXState;
+use crate::app_state::{GFXState, GameMode};
 use crate::lib::skia::{FontFamily, Skia};
 use crate::model::world_state::WorldState;
 use skia_safe::{Color, Paint, PaintStyle, Point, Rect};
@@ -16,7 +16,8 @@ pub fn army_placement(skia: &mut Skia, world_state: &WorldState, gfx: &GFXState,
     paint_title.set_anti_alias(true);
     paint_title.set_style(PaintStyle::StrokeAndFill);
     paint_title.set_color(Color::YELLOW);
-    skia.write_text_centre(30.0, &paint_title, "Regiment Placement", Point::new(l, rr.top), w, &FontFamily::EbGaramond);
+    let title = if world_state.mode == GameMode::Game { "Reinforcements" } else { "Regiment Placement" };
+    skia.write_text_centre(30.0, &paint_title, title, Point::new(l, rr.top), w, &FontFamily::EbGaramond);
 
     // Numer of armies remaining
     let mut paint_left = Paint::default();
@@ -36,8 +37,7 @@ pub fn army_placement(skia: &mut Skia, world_state: &WorldState, gfx: &GFXState,
         &FontFamily::EbGaramond,
     );
     let mut ss = String::from("");
-    let player = &world_state.current_player.as_ref().unwrap();
-    for _ in 0..player.borrow().armies_to_assign {
+    for _ in 0..world_state.get_current_player().armies_to_assign {
         ss += "⚔";
     }
     skia.write_text_centre(30.0, &paint_right, &ss, Point::new(l, rr.top + 125.0), w, &FontFamily::NotoSansSymbols);
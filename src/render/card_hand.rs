@@ -0,0 +1,107 @@
+use crate::app_state::{AppState, GFXState};
+use crate::lib::skia::{FontFamily, Skia};
+use crate::model::card::{find_set, trade_in_bonus, CardSymbol};
+use crate::render::lower_panel::lower_panel_rect;
+use skia_safe::{Color, Paint, PaintStyle, Point, Rect, Vector};
+
+const CARD_WIDTH: f32 = 56.0;
+const CARD_HEIGHT: f32 = 70.0;
+const CARD_SPACING: f32 = 64.0;
+
+pub fn trade_button_position(gfx: &GFXState) -> Vector {
+    let rr = lower_panel_rect(gfx);
+    Vector::new(gfx.half_width as f32, rr.top + 115.0)
+}
+
+fn symbol_colour(symbol: CardSymbol) -> Color {
+    match symbol {
+        CardSymbol::Infantry => Color::from_rgb(160, 60, 60),
+        CardSymbol::Cavalry => Color::from_rgb(60, 110, 160),
+        CardSymbol::Artillery => Color::from_rgb(70, 130, 70),
+        CardSymbol::Wild => Color::from_rgb(150, 120, 40),
+    }
+}
+
+pub fn card_hand(skia: &mut Skia, app_state: &AppState, rr: Rect) {
+    skia.set_matrix(&app_state.gfx);
+
+    // Positions
+    let l = rr.left + 50.0;
+    let r = rr.right - 50.0;
+    let w = r - l;
+    let t = rr.top;
+
+    let world_state = &app_state.world_state;
+    let player = world_state.get_current_player();
+
+    // Title
+    let mut paint_title = Paint::default();
+    paint_title.set_anti_alias(true);
+    paint_title.set_style(PaintStyle::StrokeAndFill);
+    paint_title.set_color(Color::YELLOW);
+    skia.write_text_centre(30.0, &paint_title, "Cards Held", Point::new(l, t), w, &FontFamily::EbGaramond);
+
+    // Paints
+    let mut paint_card = Paint::default();
+    paint_card.set_anti_alias(true);
+    paint_card.set_style(PaintStyle::Fill);
+    let mut paint_outline = Paint::default();
+    paint_outline.set_anti_alias(true);
+    paint_outline.set_style(PaintStyle::Stroke);
+    paint_outline.set_color(skia.colour_outline);
+    paint_outline.set_stroke_width(1.0);
+    let mut paint_text = Paint::default();
+    paint_text.set_anti_alias(true);
+    paint_text.set_style(PaintStyle::StrokeAndFill);
+    paint_text.set_color(Color::WHITE);
+
+    // Cards, centred in a row
+    let row_width = CARD_SPACING * player.cards.len() as f32 - (CARD_SPACING - CARD_WIDTH);
+    let mut x = l + (w - row_width) / 2.0;
+    let y = t + 40.0;
+    for card in &player.cards {
+        let rect = Rect::from_xywh(x, y, CARD_WIDTH, CARD_HEIGHT);
+        paint_card.set_color(symbol_colour(card.symbol));
+        skia.get_canvas().draw_round_rect(rect, 5.0, 5.0, &paint_card);
+        skia.get_canvas().draw_round_rect(rect, 5.0, 5.0, &paint_outline);
+        skia.write_text_centre(
+            14.0,
+            &paint_text,
+            card.symbol.name(),
+            Point::new(x, y + 6.0),
+            CARD_WIDTH,
+            &FontFamily::EbGaramondBold,
+        );
+        if let Some(city_index) = card.city {
+            skia.write_text_centre(
+                12.0,
+                &paint_text,
+                &app_state.world_fixed.cities[city_index].name,
+                Point::new(x + 2.0, y + 30.0),
+                CARD_WIDTH - 4.0,
+                &FontFamily::EbGaramond,
+            );
+        }
+        x += CARD_SPACING;
+    }
+
+    // Trade in
+    if find_set(&player.cards).is_some() {
+        let bonus = trade_in_bonus(world_state.sets_traded);
+        let mut paint_left = Paint::default();
+        paint_left.set_anti_alias(true);
+        paint_left.set_style(PaintStyle::StrokeAndFill);
+        paint_left.set_color(Color::LIGHT_GRAY);
+        skia.write_text(
+            18.0,
+            &paint_left,
+            &format!("Set worth {} regiments", bonus),
+            Point::new(r - 160.0, t + 140.0),
+            160.0,
+            &FontFamily::EbGaramond,
+        );
+        skia.button("Trade", app_state, trade_button_position(&app_state.gfx));
+    }
+
+    skia.get_canvas().restore();
+}
@@ -1,5 +1,8 @@
 use crate::app_state::AppState;
 use crate::lib::skia::{FontFamily, Skia};
+use crate::model::city::CityRole;
+use crate::model::world_state::Visibility;
+use crate::render::purchase::purchases;
 use skia_safe::{Color, Paint, PaintStyle, Point, Rect, Vector};
 
 pub fn city_selection(skia: &mut Skia, app_state: &AppState, rr: Rect) {
@@ -12,9 +15,14 @@ pub fn city_selection(skia: &mut Skia, app_state: &AppState, rr: Rect) {
     let t = rr.top;
 
     // City
-    let city = app_state.selection.last_city_selection.clone().unwrap();
-    let city_name = city.borrow().statics.borrow().name.clone();
-    let territory_name = city.borrow().statics.borrow().territory_name.clone();
+    let city_index = app_state.selection.last_city_selection.unwrap();
+    let city = &app_state.world_state.cities[city_index];
+    let statics = &app_state.world_fixed.cities[city_index];
+    let city_name = match statics.role {
+        CityRole::Town => statics.name.clone(),
+        role => format!("{} ({})", statics.name, role.name()),
+    };
+    let territory_name = &statics.territory_name;
 
     // Positions
     let text_w = 80.0;
@@ -56,7 +64,28 @@ pub fn city_selection(skia: &mut Skia, app_state: &AppState, rr: Rect) {
     skia.write_text(20.0, &paint_right, &territory_name, Point::new(text_x, t + 85.0), 0.0, &FontFamily::EbGaramond);
 
     // Combat
-    skia.button("Attack!", app_state, Vector::new(app_state.gfx.half_width as f32, t + 120.0));
+    skia.button("Attack!", app_state, Vector::new(app_state.gfx.half_width as f32 - 80.0, t + 140.0));
+
+    // Garrison
+    let visibility = app_state.world_state.visibility(&app_state.world_fixed, app_state.viewing_player());
+    let garrison = match visibility[city_index] {
+        Visibility::Full => format!("{} cavalry, walls {}", city.cavalry, city.fortification),
+        _ => "Unknown".to_string(),
+    };
+    skia.write_text_right(20.0, &paint_left, "Garrison:  ", Point::new(l, t + 110.0), text_w, &FontFamily::EbGaramond);
+    skia.write_text(20.0, &paint_right, &garrison, Point::new(text_x, t + 110.0), 0.0, &FontFamily::EbGaramond);
+
+    // Spending
+    let gold = app_state.world_state.get_current_player().gold;
+    skia.write_text_right(
+        20.0,
+        &paint_left,
+        &format!("Treasury: {} gold", gold),
+        Point::new(l, t + 30.0),
+        w,
+        &FontFamily::EbGaramond,
+    );
+    purchases(skia, app_state, city_index);
 
     // Sections
     skia.get_canvas().restore();
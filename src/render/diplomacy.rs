@@ -0,0 +1,131 @@
+use crate::app_state::{AppState, GFXState};
+use crate::lib::skia::{FontFamily, Skia};
+use crate::model::diplomacy::{DiplomacyAction, TreatyKind, TRUCE_LENGTHS};
+use crate::render::lower_panel::lower_panel_rect;
+use skia_safe::paint::Style;
+use skia_safe::{Color, Contains, Paint, Point, Rect};
+
+// Name on top, standing underneath
+const ROW_HEIGHT: f32 = 48.0;
+const CELL_WIDTH: f32 = 72.0;
+const CELL_HEIGHT: f32 = 24.0;
+
+// Room for a truce of each length and an alliance
+const COLUMNS: usize = TRUCE_LENGTHS.len() + 1;
+
+// Everyone but the viewer, a row each
+fn others(app_state: &AppState, viewer: usize) -> Vec<usize> {
+    (0..app_state.world_state.players.len()).filter(|index| *index != viewer).collect()
+}
+
+// Action cells sit on the right of a row, the first furthest left
+pub fn diplomacy_cell_rect(gfx: &GFXState, row: usize, column: usize) -> Rect {
+    let rr = lower_panel_rect(gfx);
+    Rect::from_xywh(
+        rr.right - 30.0 - CELL_WIDTH * (COLUMNS - column) as f32,
+        rr.top + ROW_HEIGHT * row as f32 + (ROW_HEIGHT - CELL_HEIGHT) / 2.0,
+        CELL_WIDTH - 4.0,
+        CELL_HEIGHT,
+    )
+}
+
+// The other player and what to do about them, if the point is on one of the action cells
+pub fn diplomacy_at(app_state: &AppState, point: Point) -> Option<(usize, DiplomacyAction)> {
+    let viewer = app_state.world_state.get_current_player_index();
+    for (row, other) in others(app_state, viewer).into_iter().enumerate() {
+        let actions = app_state.world_state.diplomacy.actions(viewer, other);
+        for (column, action) in actions.into_iter().enumerate() {
+            if diplomacy_cell_rect(&app_state.gfx, row, column).contains(point) {
+                return Some((other, action));
+            }
+        }
+    }
+    None
+}
+
+// Where the viewer stands with each other player, with offers and answers while it's their go
+pub fn diplomacy(skia: &mut Skia, app_state: &AppState, rr: Rect) {
+    let Some(viewer) = app_state.viewing_player() else {
+        return;
+    };
+    let world_state = &app_state.world_state;
+    let world_fixed = &app_state.world_fixed;
+    let can_act = world_state.get_current_player_index() == viewer;
+    skia.set_matrix(&app_state.gfx);
+
+    let mut paint_white = Paint::default();
+    paint_white.set_anti_alias(true);
+    paint_white.set_style(Style::StrokeAndFill);
+    paint_white.set_color(Color::WHITE);
+    let mut paint_grey = paint_white.clone();
+    paint_grey.set_color(Color::LIGHT_GRAY);
+    let mut paint_border = Paint::default();
+    paint_border.set_anti_alias(true);
+    paint_border.set_style(Style::Stroke);
+    paint_border.set_stroke_width(1.0);
+    paint_border.set_color(skia.colour_outline);
+    let mut paint_hover = Paint::default();
+    paint_hover.set_anti_alias(true);
+    paint_hover.set_style(Style::Fill);
+    paint_hover.set_color(skia.colour_popup);
+
+    for (row, other) in others(app_state, viewer).into_iter().enumerate() {
+        let y = rr.top + ROW_HEIGHT * row as f32;
+
+        let mut paint_player = Paint::default();
+        paint_player.set_anti_alias(true);
+        paint_player.set_style(Style::Fill);
+        paint_player.set_color(world_fixed.players[other].colours[0]);
+        skia.get_canvas().draw_circle(Point::new(rr.left + 52.0, y + 13.0), 7.0, &paint_player);
+        skia.get_canvas().draw_circle(Point::new(rr.left + 52.0, y + 13.0), 9.0, &paint_border);
+        skia.write_text(
+            18.0,
+            &paint_white,
+            &world_fixed.players[other].name,
+            Point::new(rr.left + 66.0, y),
+            0.0,
+            &FontFamily::EbGaramond,
+        );
+
+        // Offers waiting either way take precedence over what's already agreed
+        let diplomacy = &world_state.diplomacy;
+        let status = if let Some(proposal) = diplomacy.proposal(other, viewer) {
+            match proposal.kind {
+                TreatyKind::Truce => format!("Offers a {} turn truce", proposal.turns),
+                TreatyKind::Alliance => "Offers alliance".to_string(),
+            }
+        } else if let Some(proposal) = diplomacy.proposal(viewer, other) {
+            match proposal.kind {
+                TreatyKind::Truce => format!("{} turn truce offered", proposal.turns),
+                TreatyKind::Alliance => "Alliance offered".to_string(),
+            }
+        } else {
+            match diplomacy.treaty(viewer, other) {
+                Some(treaty) if treaty.kind == TreatyKind::Truce => format!("Truce, {} turns", treaty.turns_left),
+                Some(_) => "Allied".to_string(),
+                None => "At war".to_string(),
+            }
+        };
+        skia.write_text(16.0, &paint_grey, &status, Point::new(rr.left + 66.0, y + 22.0), 0.0, &FontFamily::EbGaramond);
+
+        if !can_act {
+            continue;
+        }
+        for (column, action) in diplomacy.actions(viewer, other).into_iter().enumerate() {
+            let rect = diplomacy_cell_rect(&app_state.gfx, row, column);
+            if rect.contains(app_state.hover) {
+                skia.get_canvas().draw_round_rect(rect, 4.0, 4.0, &paint_hover);
+            }
+            skia.write_text_centre(
+                16.0,
+                &paint_white,
+                &action.name(),
+                Point::new(rect.left, rect.top + 2.0),
+                rect.width(),
+                &FontFamily::EbGaramond,
+            );
+        }
+    }
+
+    skia.get_canvas().restore();
+}
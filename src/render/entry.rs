@@ -1,22 +1,32 @@
+use crate::ai::moves::MoveType;
 use crate::app_state::{AppState, GameMode};
+use crate::camera::view_rect;
+use crate::input::placing_armies;
 use crate::lib::skia;
 use crate::lib::skia::{FontFamily, Skia};
-use crate::model::city::{MAXIMUM_LABEL_WIDTH, SIZE, SIZE_SELECTED};
+use crate::model::card::find_set;
+use crate::model::city::{MAXIMUM_LABEL_WIDTH, ROLE_ICON_SIZE, SIZE, SIZE_SELECTED};
 use crate::model::connection::LINE_WIDTH;
+use crate::model::simplify::level_for_scale;
 use crate::model::world_fixed::WorldFixed;
-use crate::model::world_state::WorldState;
+use crate::model::world_state::Visibility;
 use crate::render::army_placement::army_placement;
+use crate::render::card_hand::card_hand;
 use crate::render::city_selection::city_selection;
+use crate::render::diplomacy::diplomacy;
 use crate::render::lower_panel::render_lower_panel;
+use crate::render::map_style::render_map;
+use crate::render::minimap::render_minimap;
 use crate::render::randomising::randomising;
 use crate::render::region_summary::region_summary;
+use crate::render::setup::render_setup;
 use crate::render::surround::render_surround;
 use crate::render::title_bar::render_title_bar;
+use crate::render::turn_controls::turn_controls;
 use skia_safe::textlayout::TextAlign;
 use skia_safe::{dash_path_effect, Color, Paint, PaintStyle, Point, RRect, Rect};
-use std::rc::Rc;
 
-fn render_connections(skia: &mut Skia, world_state: &WorldState, world_fixed: &mut WorldFixed) {
+fn render_connections(skia: &mut Skia, world_fixed: &WorldFixed, view: Rect) {
     // Paint
     let mut paint = Paint::default();
     paint.set_anti_alias(true);
@@ -32,54 +42,35 @@ fn render_connections(skia: &mut Skia, world_state: &WorldState, world_fixed: &m
     paint.set_path_effect(dash_path_effect::new(&[1.0, 1.0], phase).unwrap());
     paint_alt.set_path_effect(dash_path_effect::new(&[1.0, 1.0], phase + 1.0).unwrap());
 
-    for connection in &world_fixed.connections {
+    for connection_index in world_fixed.index.connections.query(view) {
+        let connection = &world_fixed.connections[connection_index];
         let city1_index = connection.city1;
         let city2_index = connection.city2;
-        let city1 = &world_state.cities[city1_index];
-        let city2 = &world_state.cities[city2_index];
+        let city1 = &world_fixed.cities[city1_index];
+        let city2 = &world_fixed.cities[city2_index];
 
-        skia.get_canvas().draw_line(
-            city1.borrow().statics.borrow().location.p,
-            city2.borrow().statics.borrow().location.p,
-            &paint,
-        );
-        skia.get_canvas().draw_line(
-            city1.borrow().statics.borrow().location.p,
-            city2.borrow().statics.borrow().location.p,
-            &paint_alt,
-        );
-    }
-}
-
-fn render_territories(skia: &mut Skia, world_fixed: &WorldFixed) {
-    for territory in world_fixed.territories.values() {
-        for polygon in &territory.polygons {
-            skia.get_canvas().draw_picture(polygon.pic.as_ref(), None, None);
-        }
+        skia.get_canvas().draw_line(city1.location.p, city2.location.p, &paint);
+        skia.get_canvas().draw_line(city1.location.p, city2.location.p, &paint_alt);
     }
 }
 
-fn render_cities(skia: &mut Skia, app_state: &AppState) {
+fn render_cities(skia: &mut Skia, app_state: &AppState, view: Rect) {
     let world_state = &app_state.world_state;
     let world_fixed = &app_state.world_fixed;
-    let is_human = if world_state.current_player.is_none() {
-        false
-    } else {
-        world_state.current_player.as_ref().unwrap().borrow().is_human()
-    };
-    for city in &world_state.cities {
-        let selected = if let Some(selected) = &app_state.selection.last_city_selection {
-            is_human && Rc::ptr_eq(city, selected)
-        } else {
-            false
-        };
-        let hover = if let Some(hover) = &app_state.selection.last_city_hover {
-            is_human && Rc::ptr_eq(city, hover)
-        } else {
-            false
-        };
-        let centre = city.borrow().statics.borrow().location.p;
-        let territory = world_fixed.territories.get(&city.borrow().statics.borrow().territory_name).unwrap();
+    let is_human = world_state.get_current_player().is_human();
+    let visibility = world_state.visibility(world_fixed, app_state.viewing_player());
+
+    // Labels and icons reach past the city itself
+    let margin = MAXIMUM_LABEL_WIDTH + ROLE_ICON_SIZE;
+    for city_index in world_fixed.index.cities.query(view.with_outset((margin, margin))) {
+        let city = &world_state.cities[city_index];
+        let visible = visibility[city_index];
+        let statics = &world_fixed.cities[city_index];
+        let selected = is_human && app_state.selection.last_city_selection == Some(city_index);
+        let hover = is_human && app_state.selection.last_city_hover == Some(city_index);
+        let targeted = is_human && app_state.selection.attack_target == Some(city_index);
+        let centre = statics.location.p;
+        let territory = world_fixed.territories.get(&statics.territory_name).unwrap();
         let font_size: f32 = 2.4;
 
         let mut paint_name = Paint::default();
@@ -94,8 +85,9 @@ fn render_cities(skia: &mut Skia, app_state: &AppState) {
         paint_fill.set_color(skia::mix_colors(territory.colour, Color::WHITE, 0.6));
         let mut paint_fill_circle = Paint::default();
         paint_fill_circle.set_style(PaintStyle::Fill);
-        let colours = match city.borrow().owner {
-            Some(x) => world_state.get_player_for_index(x).borrow().colours.clone(),
+        let owner = if visible == Visibility::Hidden { None } else { city.owner };
+        let colours = match owner {
+            Some(x) => world_fixed.players[x].colours.clone(),
             None => vec![Color::from_rgb(128, 128, 128), Color::BLACK],
         };
         paint_fill_circle.set_color(colours[0]);
@@ -112,13 +104,7 @@ fn render_cities(skia: &mut Skia, app_state: &AppState) {
         // Name background
         if app_state.show_all_info() {
             let dimensions = skia
-                .text_dimensions(
-                    font_size,
-                    &paint_name,
-                    &city.borrow().statics.borrow().name,
-                    &FontFamily::EbGaramond,
-                    TextAlign::Left,
-                )
+                .text_dimensions(font_size, &paint_name, &statics.name, &FontFamily::EbGaramond, TextAlign::Left)
                 .clamp(1.0, MAXIMUM_LABEL_WIDTH);
             if app_state.show_shadows {
                 skia.get_canvas().draw_round_rect(
@@ -152,7 +138,50 @@ fn render_cities(skia: &mut Skia, app_state: &AppState) {
             paint_outline.set_path_effect(dash_path_effect::new(&[0.5, 0.5], app_state.phase).unwrap());
         }
         skia.get_canvas().draw_circle(centre, SIZE, &paint_outline);
-        let strength = format!("{}/{}", city.borrow().armies, city.borrow().size);
+
+        // Each level of walls thickens the outline
+        let fortification = city.fortification;
+        if fortification > 0 && visible == Visibility::Full {
+            let mut paint_walls = paint_outline.clone();
+            paint_walls.set_path_effect(None);
+            paint_walls.set_stroke_width(SIZE / 8.0 * fortification as f32);
+            skia.get_canvas().draw_circle(centre, SIZE * 0.8, &paint_walls);
+        }
+
+        // Growth towards the next size, red while under siege
+        let growth = city.growth;
+        if growth > 0.0 && visible == Visibility::Full {
+            let mut paint_growth = Paint::default();
+            paint_growth.set_anti_alias(true);
+            paint_growth.set_style(PaintStyle::Stroke);
+            paint_growth.set_stroke_width(SIZE / 8.0);
+            paint_growth.set_color(if city.besieged { Color::RED } else { Color::WHITE });
+            let oval = Rect::from_xywh(centre.x - SIZE * 1.2, centre.y - SIZE * 1.2, SIZE * 2.4, SIZE * 2.4);
+            skia.get_canvas().draw_arc(oval, -90.0, 360.0 * growth, false, &paint_growth);
+        }
+
+        // Role icon sits on top of the city
+        let role = statics.role;
+        if let Some(image) = app_state.res.role_image(role) {
+            let mut paint_image = Paint::default();
+            paint_image.set_anti_alias(true);
+            skia.get_canvas().draw_image_rect(
+                image,
+                None,
+                Rect::from_xywh(
+                    centre.x - ROLE_ICON_SIZE / 2.0,
+                    centre.y - SIZE - ROLE_ICON_SIZE,
+                    ROLE_ICON_SIZE,
+                    ROLE_ICON_SIZE,
+                ),
+                &paint_image,
+            );
+        }
+        let strength = match visible {
+            Visibility::Full => format!("{}/{}", city.armies, city.size),
+            Visibility::Owner => format!("?/{}", city.size),
+            Visibility::Hidden => "?".to_string(),
+        };
         skia.write_text_centre(
             5.0,
             &paint_number,
@@ -165,7 +194,7 @@ fn render_cities(skia: &mut Skia, app_state: &AppState) {
             skia.write_text(
                 font_size,
                 &paint_name,
-                &city.borrow().statics.borrow().name,
+                &statics.name,
                 Point::new(centre.x + SIZE + 0.5, centre.y - 1.2),
                 MAXIMUM_LABEL_WIDTH,
                 &FontFamily::EbGaramond,
@@ -180,12 +209,75 @@ fn render_cities(skia: &mut Skia, app_state: &AppState) {
             paint_selected.set_path_effect(dash_path_effect::new(&[5.0, 5.0], app_state.phase * 5.0).unwrap());
             skia.get_canvas().draw_circle(centre, SIZE_SELECTED, &paint_selected);
         }
+
+        // Where the keys or controller will attack from the selected city
+        if targeted {
+            let mut paint_target = Paint::default();
+            paint_target.set_anti_alias(true);
+            paint_target.set_style(PaintStyle::Stroke);
+            paint_target.set_color(Color::RED);
+            paint_target.set_stroke_width(SIZE / 4.0);
+            paint_target.set_path_effect(dash_path_effect::new(&[5.0, 5.0], -app_state.phase * 5.0).unwrap());
+            skia.get_canvas().draw_circle(centre, SIZE_SELECTED, &paint_target);
+        }
+    }
+}
+
+// Play out the computer's current move, a line growing towards an attacked city or a ring around anything else
+fn render_ai_move(skia: &mut Skia, app_state: &AppState) {
+    let Some((the_move, progress)) = app_state.ai_turn.current_move() else {
+        return;
+    };
+    let world_fixed = &app_state.world_fixed;
+    let visibility = app_state.world_state.visibility(world_fixed, app_state.viewing_player());
+    let colour = world_fixed.players[app_state.world_state.current_player].colours[0];
+
+    let mut paint = Paint::default();
+    paint.set_anti_alias(true);
+    paint.set_style(PaintStyle::Stroke);
+    paint.set_color(colour);
+    paint.set_stroke_width(SIZE / 3.0);
+
+    match (the_move.move_type, the_move.city_source, the_move.city_target) {
+        (MoveType::AttackCity | MoveType::CavalryAttack, Some(source), Some(target)) => {
+            if visibility[source] == Visibility::Hidden && visibility[target] == Visibility::Hidden {
+                return;
+            }
+            let from = world_fixed.cities[source].location.p;
+            let to = world_fixed.cities[target].location.p;
+            let head = from + (to - from) * progress;
+            skia.get_canvas().draw_line(from, head, &paint);
+            skia.get_canvas().draw_circle(head, SIZE / 2.0, &paint);
+        }
+        (_, source, target) => {
+            let Some(city_index) = target.or(source) else {
+                return;
+            };
+            if visibility[city_index] == Visibility::Hidden {
+                return;
+            }
+            paint.set_alpha_f(1.0 - progress);
+            skia.get_canvas().draw_circle(
+                world_fixed.cities[city_index].location.p,
+                SIZE + SIZE_SELECTED * progress,
+                &paint,
+            );
+        }
     }
 }
 
 pub fn main(skia: &mut Skia, app_state: &mut AppState) {
     skia.reset_context();
 
+    // Nothing to show of the world until it's been set up
+    if app_state.world_state.mode == GameMode::Setup {
+        render_setup(skia, app_state);
+        unsafe {
+            skia.flush();
+        }
+        return;
+    }
+
     let clip_rect = RRect::new_rect_xy(
         Rect::from_xywh(32.0, 32.0, app_state.gfx.width as f32 - 64.0, app_state.gfx.height as f32 - 64.0),
         128.0,
@@ -204,13 +296,17 @@ pub fn main(skia: &mut Skia, app_state: &mut AppState) {
     canvas.scale((app_state.zoom, app_state.zoom));
     canvas.translate((-app_state.target.x, -app_state.target.y));
 
-    // Now render parts
+    // Now render parts, only those in view
+    let viewer = app_state.viewing_player();
+    app_state.region_tints.update(&app_state.world_fixed, &app_state.world_state, viewer);
     {
-        let world_state = &app_state.world_state;
-        let world_fixed = &mut app_state.world_fixed;
-        render_territories(skia, world_fixed);
-        render_connections(skia, world_state, world_fixed);
-        render_cities(skia, app_state);
+        let world_fixed = &app_state.world_fixed;
+        let view = view_rect(app_state);
+        let level = level_for_scale(app_state.zoom * app_state.gfx.dpi);
+        render_map(skia, app_state, view, level);
+        render_connections(skia, world_fixed, view);
+        render_cities(skia, app_state, view);
+        render_ai_move(skia, app_state);
     }
 
     skia.clear_matrix();
@@ -218,26 +314,36 @@ pub fn main(skia: &mut Skia, app_state: &mut AppState) {
     // Elements
     let rr = render_lower_panel(skia, app_state);
     render_surround(skia, app_state, clip_rect);
+    render_minimap(skia, app_state);
     render_title_bar(skia, app_state);
 
     // Now, render based on mode
     {
         let world_state = &app_state.world_state;
         match world_state.mode {
-            GameMode::Randomising => {
+            GameMode::Randomising | GameMode::Draft => {
                 randomising(skia, app_state, rr);
             }
             GameMode::ArmyPlacement => {
-                if world_state.current_player.as_ref().unwrap().borrow().is_human() {
+                if world_state.get_current_player().is_human() {
                     army_placement(skia, world_state, &app_state.gfx, rr);
                 } else {
                     region_summary(skia, app_state, rr);
                 }
             }
             GameMode::Game => {
-                if world_state.current_player.as_ref().unwrap().borrow().is_human() {
-                    if app_state.selection.last_city_selection.is_some() {
+                if app_state.show_diplomacy {
+                    diplomacy(skia, app_state, rr);
+                } else if world_state.get_current_player().is_human() {
+                    // Armies to place first, though the cards are shown away from our cities if a set can be traded
+                    let selected = app_state.selection.last_city_selection.is_some();
+                    let can_trade = find_set(&world_state.get_current_player().cards).is_some();
+                    if placing_armies(world_state) && (selected || !can_trade) {
+                        army_placement(skia, world_state, &app_state.gfx, rr);
+                    } else if selected {
                         city_selection(skia, app_state, rr);
+                    } else if !world_state.get_current_player().cards.is_empty() {
+                        card_hand(skia, app_state, rr);
                     } else {
                         region_summary(skia, app_state, rr);
                     }
@@ -245,9 +351,10 @@ pub fn main(skia: &mut Skia, app_state: &mut AppState) {
                     region_summary(skia, app_state, rr);
                 }
             }
-            GameMode::End => {}
+            GameMode::Setup | GameMode::End => {}
         }
     }
+    turn_controls(skia, app_state);
 
     // FPS
     /*
@@ -1,4 +1,4 @@
-use crate::app_state::{AppState, NOISE_MIX};
+use crate::app_state::{AppState, GFXState, NOISE_MIX};
 use crate::lib::skia::Skia;
 use skia_safe::paint::Style;
 use skia_safe::{Paint, Rect, Vector};
@@ -6,13 +6,17 @@ use skia_safe::{Paint, Rect, Vector};
 const WIDTH: f32 = 275.0;
 const HEIGHT: f32 = 200.0;
 
+pub fn lower_panel_rect(gfx: &GFXState) -> Rect {
+    let l = gfx.half_width as f32 - WIDTH;
+    let r = gfx.half_width as f32 + WIDTH;
+    let t = gfx.height as f32 - HEIGHT - 32.0;
+    let b = t + HEIGHT;
+    Rect::new(l, t, r, b)
+}
+
 pub fn render_lower_panel(skia: &mut Skia, app_state: &mut AppState) -> Rect {
     // Bottom section for drawing stuff
-    let l = app_state.gfx.half_width as f32 - WIDTH;
-    let r = app_state.gfx.half_width as f32 + WIDTH;
-    let t = app_state.gfx.height as f32 - HEIGHT - 32.0;
-    let b = t + HEIGHT;
-    let rr = Rect::new(l, t, r, b);
+    let rr = lower_panel_rect(&app_state.gfx);
 
     // Outer shape
     skia.set_matrix(&app_state.gfx);
@@ -0,0 +1,225 @@
+use crate::app_state::{AppState, GameMode};
+use crate::lib::skia;
+use crate::lib::skia::Skia;
+use crate::model::world_fixed::WorldFixed;
+use crate::model::world_state::{Visibility, WorldState};
+use skia_safe::{dash_path_effect, Color, Paint, PaintStyle, Picture, PictureRecorder, Rect};
+
+// How the map itself is drawn, switched between while playing
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MapTheme {
+    Parchment,
+    #[default]
+    Modern,
+    Halftone,
+}
+
+impl MapTheme {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MapTheme::Parchment => "Parchment",
+            MapTheme::Modern => "Modern",
+            MapTheme::Halftone => "Halftone",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            MapTheme::Parchment => MapTheme::Modern,
+            MapTheme::Modern => MapTheme::Halftone,
+            MapTheme::Halftone => MapTheme::Parchment,
+        }
+    }
+
+    pub fn style(self) -> MapStyle {
+        match self {
+            MapTheme::Parchment => MapStyle {
+                sea: Color::from_rgb(160, 188, 190),
+                sea_texture: SeaTexture::Noise(0.15),
+                paper: Color::from_rgb(236, 222, 188),
+                land_mix: 0.55,
+                halftone_land: false,
+                coast: Color::from_rgb(96, 72, 40),
+                coast_width: 3.0,
+                border: Color::from_rgb(110, 80, 45),
+                border_width: 1.2,
+                border_dashed: true,
+                tint: 0.35,
+            },
+            MapTheme::Modern => MapStyle {
+                sea: Color::from_rgb(34, 62, 98),
+                sea_texture: SeaTexture::Noise(0.04),
+                paper: Color::WHITE,
+                land_mix: 0.0,
+                halftone_land: false,
+                coast: Color::from_rgb(220, 235, 255),
+                coast_width: 2.0,
+                border: Color::from_rgb(30, 30, 30),
+                border_width: 1.0,
+                border_dashed: false,
+                tint: 0.45,
+            },
+            MapTheme::Halftone => MapStyle {
+                sea: Color::from_rgb(70, 100, 150),
+                sea_texture: SeaTexture::Halftone,
+                paper: Color::from_rgb(240, 236, 225),
+                land_mix: 0.0,
+                halftone_land: true,
+                coast: Color::BLACK,
+                coast_width: 2.5,
+                border: Color::BLACK,
+                border_width: 1.0,
+                border_dashed: false,
+                tint: 0.3,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeaTexture {
+    Noise(f32),
+    Halftone,
+}
+
+// Colours and line widths for a theme, widths are in screen pixels whatever the zoom
+#[derive(Debug, Clone)]
+pub struct MapStyle {
+    pub sea: Color,
+    pub sea_texture: SeaTexture,
+    pub paper: Color,
+    // How far each territory's colour is faded towards the paper
+    pub land_mix: f32,
+    pub halftone_land: bool,
+    pub coast: Color,
+    pub coast_width: f32,
+    pub border: Color,
+    pub border_width: f32,
+    pub border_dashed: bool,
+    // Opacity of each owner's colour over the cities' regions
+    pub tint: f32,
+}
+
+// Each city's region filled in its owner's colour, as far as the viewer knows. Only redrawn for cities that
+// have changed hands since last time
+#[derive(Debug, Default)]
+pub struct RegionTints {
+    drawn_for: Option<(u32, Option<usize>, GameMode)>,
+    owners: Vec<Option<usize>>,
+    pics: Vec<Option<Picture>>,
+}
+
+impl RegionTints {
+    pub fn update(&mut self, world_fixed: &WorldFixed, world_state: &WorldState, viewer: Option<usize>) {
+        // Nothing to do unless a city has changed hands, or someone else is looking
+        let drawn_for = Some((world_state.ownership_changes, viewer, world_state.mode.clone()));
+        if self.drawn_for == drawn_for {
+            return;
+        }
+        self.drawn_for = drawn_for;
+        self.owners.resize(world_fixed.regions.len(), None);
+        self.pics.resize(world_fixed.regions.len(), None);
+
+        let visibility = world_state.visibility(world_fixed, viewer);
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Fill);
+        for (city_index, region) in world_fixed.regions.iter().enumerate() {
+            let owner = match visibility[city_index] {
+                Visibility::Hidden => None,
+                _ => world_state.cities[city_index].owner,
+            };
+            if owner == self.owners[city_index] {
+                continue;
+            }
+            self.owners[city_index] = owner;
+            self.pics[city_index] = owner.and_then(|owner| {
+                paint.set_color(world_fixed.players[owner].colours[0]);
+                let mut recorder = PictureRecorder::new();
+                let canvas = recorder.begin_recording(*region.bounds(), None);
+                canvas.draw_path(region, &paint);
+                recorder.finish_recording_as_picture(None)
+            });
+        }
+    }
+
+    // All at once through one layer, so the tint is even where regions meet
+    pub fn render(&self, skia: &mut Skia, world_fixed: &WorldFixed, view: Rect, alpha: f32) {
+        let canvas = skia.get_canvas();
+        canvas.save_layer_alpha_f(view, alpha);
+        for (city_index, pic) in self.pics.iter().enumerate() {
+            if let Some(pic) = pic {
+                if world_fixed.regions[city_index].bounds().intersects(view) {
+                    canvas.draw_picture(pic, None, None);
+                }
+            }
+        }
+        canvas.restore();
+    }
+}
+
+// Sea, coastlines, land, who owns what and the borders between territories, for whatever's in view
+pub fn render_map(skia: &mut Skia, app_state: &AppState, view: Rect, level: usize) {
+    let style = app_state.map_theme.style();
+    let world_fixed = &app_state.world_fixed;
+    let pixel = 1.0 / app_state.zoom;
+    let polygons = world_fixed.index.polygons.query(view);
+
+    // Sea
+    let mut paint_sea = Paint::default();
+    paint_sea.set_style(PaintStyle::Fill);
+    match style.sea_texture {
+        SeaTexture::Noise(mix) => paint_sea.set_shader(skia.create_noise_shader(style.sea, mix)),
+        SeaTexture::Halftone => paint_sea.set_shader(skia.halftone(style.sea, style.paper)),
+    };
+    skia.get_canvas().draw_rect(view, &paint_sea);
+
+    // Coastlines, a wide stroke with the land drawn over its inner half
+    let mut paint_coast = Paint::default();
+    paint_coast.set_anti_alias(true);
+    paint_coast.set_style(PaintStyle::Stroke);
+    paint_coast.set_color(style.coast);
+    paint_coast.set_stroke_width(style.coast_width * 2.0 * pixel);
+    for polygon_index in &polygons {
+        let (territory, index) = &world_fixed.index.polygon_keys[*polygon_index];
+        skia.get_canvas().draw_path(&territory.polygons[*index].paths[level], &paint_coast);
+    }
+
+    // Land
+    for polygon_index in &polygons {
+        let (territory, index) = &world_fixed.index.polygon_keys[*polygon_index];
+        let polygon = &territory.polygons[*index];
+        if style.halftone_land {
+            let mut paint_land = Paint::default();
+            paint_land.set_anti_alias(true);
+            paint_land.set_style(PaintStyle::Fill);
+            paint_land.set_shader(skia.halftone(territory.colour, style.paper));
+            skia.get_canvas().draw_path(&polygon.paths[level], &paint_land);
+        } else if style.land_mix > 0.0 {
+            let mut paint_land = Paint::default();
+            paint_land.set_anti_alias(true);
+            paint_land.set_style(PaintStyle::Fill);
+            paint_land.set_color(skia::mix_colors(territory.colour, style.paper, style.land_mix));
+            skia.get_canvas().draw_path(&polygon.paths[level], &paint_land);
+        } else {
+            skia.get_canvas().draw_picture(polygon.pic(level), None, None);
+        }
+    }
+
+    // Ownership
+    app_state.region_tints.render(skia, world_fixed, view, style.tint);
+
+    // Borders between territories
+    let mut paint_border = Paint::default();
+    paint_border.set_anti_alias(true);
+    paint_border.set_style(PaintStyle::Stroke);
+    paint_border.set_color(style.border);
+    paint_border.set_stroke_width(style.border_width * pixel);
+    if style.border_dashed {
+        paint_border.set_path_effect(dash_path_effect::new(&[4.0 * pixel, 3.0 * pixel], 0.0).unwrap());
+    }
+    for polygon_index in &polygons {
+        let (territory, index) = &world_fixed.index.polygon_keys[*polygon_index];
+        skia.get_canvas().draw_path(&territory.polygons[*index].paths[level], &paint_border);
+    }
+}
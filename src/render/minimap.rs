@@ -0,0 +1,114 @@
+use crate::app_state::{AppState, GFXState, NOISE_MIX};
+use crate::camera::view_rect;
+use crate::lib::skia::Skia;
+use crate::model::simplify::level_for_scale;
+use crate::model::world_state::Visibility;
+use skia_safe::paint::Style;
+use skia_safe::{Color, Contains, Paint, Point, Rect, Vector};
+
+const WIDTH: f32 = 240.0;
+const HEIGHT: f32 = 150.0;
+const MARGIN: f32 = 56.0;
+const DOT: f32 = 2.5;
+
+// Tucked into the bottom-left corner of the surround
+pub fn minimap_rect(gfx: &GFXState) -> Rect {
+    Rect::from_xywh(MARGIN, gfx.height as f32 - MARGIN - HEIGHT, WIDTH, HEIGHT)
+}
+
+// Scale and offset fitting the whole map in the panel, keeping its shape
+fn minimap_transform(app_state: &AppState) -> (f32, Vector) {
+    let rr = minimap_rect(&app_state.gfx);
+    let bounds = app_state.camera.bounds;
+    if bounds.is_empty() {
+        return (1.0, Vector::new(rr.center_x(), rr.center_y()));
+    }
+    let scale = (rr.width() / bounds.width()).min(rr.height() / bounds.height());
+    let offset = Vector::new(rr.center_x() - bounds.center_x() * scale, rr.center_y() - bounds.center_y() * scale);
+    (scale, offset)
+}
+
+fn world_to_minimap(app_state: &AppState, point: Point) -> Point {
+    let (scale, offset) = minimap_transform(app_state);
+    point * scale + offset
+}
+
+// Map coordinates of a point on the minimap, if it's on it
+pub fn minimap_to_world(app_state: &AppState, point: Point) -> Option<Point> {
+    if !minimap_rect(&app_state.gfx).contains(point) {
+        return None;
+    }
+    let (scale, offset) = minimap_transform(app_state);
+    Some((point - offset) * (1.0 / scale))
+}
+
+// The whole map in small, who has each city, and the part of it in view
+pub fn render_minimap(skia: &mut Skia, app_state: &AppState) {
+    let rr = minimap_rect(&app_state.gfx);
+    let world_state = &app_state.world_state;
+    let world_fixed = &app_state.world_fixed;
+    skia.set_matrix(&app_state.gfx);
+
+    // Panel
+    let mut paint_background = Paint::default();
+    paint_background.set_anti_alias(true);
+    paint_background.set_style(Style::Fill);
+    paint_background.set_shader(skia.create_noise_shader(skia.colour_background, NOISE_MIX));
+    let panel = Rect::from_xywh(rr.left - 8.0, rr.top - 8.0, rr.width() + 16.0, rr.height() + 16.0);
+    skia.get_canvas().draw_round_rect(panel, 16.0, 16.0, &paint_background);
+    let mut paint_border = Paint::default();
+    paint_border.set_anti_alias(true);
+    paint_border.set_style(Style::Stroke);
+    paint_border.set_stroke_width(1.0);
+    paint_border.set_color(skia.colour_outline);
+    skia.get_canvas().draw_round_rect(panel, 16.0, 16.0, &paint_border);
+
+    // Territories and who holds them, the same pictures as the main view
+    let (scale, offset) = minimap_transform(app_state);
+    let canvas = skia.get_canvas();
+    canvas.save();
+    canvas.clip_rect(rr, None, true);
+    canvas.translate(offset);
+    canvas.scale((scale, scale));
+    let level = level_for_scale(scale * app_state.gfx.dpi);
+    for territory in world_fixed.territories.values() {
+        for polygon in &territory.polygons {
+            canvas.draw_picture(polygon.pic(level), None, None);
+        }
+    }
+    app_state.region_tints.render(skia, world_fixed, app_state.camera.bounds, app_state.map_theme.style().tint);
+    skia.get_canvas().restore();
+
+    // Cities in their owner's colour, as much as the viewer knows
+    let visibility = world_state.visibility(world_fixed, app_state.viewing_player());
+    let mut paint_city = Paint::default();
+    paint_city.set_anti_alias(true);
+    paint_city.set_style(Style::Fill);
+    for (city_index, city) in world_state.cities.iter().enumerate() {
+        let owner = if visibility[city_index] == Visibility::Hidden { None } else { city.owner };
+        let colour = match owner {
+            Some(owner) => world_fixed.players[owner].colours[0],
+            None => Color::from_rgb(128, 128, 128),
+        };
+        paint_city.set_color(colour);
+        let centre = world_to_minimap(app_state, world_fixed.cities[city_index].location.p);
+        skia.get_canvas().draw_circle(centre, DOT, &paint_city);
+    }
+
+    // What the main view is showing
+    let view = view_rect(app_state);
+    let top_left = world_to_minimap(app_state, Point::new(view.left, view.top));
+    let bottom_right = world_to_minimap(app_state, Point::new(view.right, view.bottom));
+    let mut paint_view = Paint::default();
+    paint_view.set_anti_alias(true);
+    paint_view.set_style(Style::Stroke);
+    paint_view.set_stroke_width(1.5);
+    paint_view.set_color(Color::WHITE);
+    let canvas = skia.get_canvas();
+    canvas.save();
+    canvas.clip_rect(rr, None, true);
+    canvas.draw_rect(Rect::new(top_left.x, top_left.y, bottom_right.x, bottom_right.y), &paint_view);
+    canvas.restore();
+
+    skia.get_canvas().restore();
+}
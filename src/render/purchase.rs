@@ -0,0 +1,57 @@
+use crate::app_state::{AppState, GFXState};
+use crate::lib::skia::{FontFamily, Skia};
+use crate::model::economy::{Purchase, PURCHASES};
+use crate::render::lower_panel::lower_panel_rect;
+use skia_safe::{Color, Contains, Paint, PaintStyle, Point, Rect};
+
+const ROW_WIDTH: f32 = 220.0;
+const ROW_HEIGHT: f32 = 24.0;
+
+pub fn purchase_row_rect(gfx: &GFXState, index: usize) -> Rect {
+    let rr = lower_panel_rect(gfx);
+    Rect::from_xywh(rr.right - 50.0 - ROW_WIDTH, rr.top + 58.0 + index as f32 * 25.0, ROW_WIDTH, ROW_HEIGHT)
+}
+
+pub fn purchase_at(gfx: &GFXState, point: Point) -> Option<Purchase> {
+    (0..PURCHASES.len()).find(|index| purchase_row_rect(gfx, *index).contains(point)).map(|index| PURCHASES[index])
+}
+
+// A row per purchase for the selected city, greyed out when unaffordable
+pub fn purchases(skia: &mut Skia, app_state: &AppState, city_index: usize) {
+    let city = &app_state.world_state.cities[city_index];
+    let gold = app_state.world_state.get_current_player().gold;
+
+    let mut paint_hover = Paint::default();
+    paint_hover.set_anti_alias(true);
+    paint_hover.set_style(PaintStyle::Fill);
+    paint_hover.set_color(skia.colour_popup);
+    let mut paint_text = Paint::default();
+    paint_text.set_anti_alias(true);
+    paint_text.set_style(PaintStyle::StrokeAndFill);
+
+    for (index, purchase) in PURCHASES.iter().enumerate() {
+        let rect = purchase_row_rect(&app_state.gfx, index);
+        let label = match purchase {
+            Purchase::Upgrade => format!("Upgrade to size {}", city.size + 1),
+            _ => purchase.name().to_string(),
+        };
+        let (cost, affordable) = match purchase.cost(city) {
+            Some(cost) => (format!("{} gold", cost), cost <= gold),
+            None => ("-".to_string(), false),
+        };
+        if affordable && rect.contains(app_state.hover) {
+            skia.get_canvas().draw_round_rect(rect, 4.0, 4.0, &paint_hover);
+        }
+        paint_text.set_color(if affordable { Color::WHITE } else { Color::GRAY });
+        let text_y = rect.top + 1.0;
+        skia.write_text(18.0, &paint_text, &label, Point::new(rect.left + 6.0, text_y), 0.0, &FontFamily::EbGaramond);
+        skia.write_text_right(
+            18.0,
+            &paint_text,
+            &cost,
+            Point::new(rect.left, text_y),
+            rect.width() - 6.0,
+            &FontFamily::EbGaramond,
+        );
+    }
+}
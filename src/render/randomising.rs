@@ -1,15 +1,25 @@
-use crate::app_state::AppState;
+use crate::app_state::{AppState, GameMode};
 use crate::lib::skia::{FontFamily, Skia};
 use skia_safe::PaintStyle;
 use skia_safe::{Color, Paint, Point, Rect};
 
 pub fn assign(app_state: &mut AppState) {
-    let world_state = &app_state.world_state;
-    let next_city = app_state.world_fixed.cities_to_assign.pop().unwrap();
-    next_city.borrow_mut().owner = Some(world_state.get_current_player_index());
+    let world_state = &mut app_state.world_state;
+    let next_city = world_state.cities_to_assign.pop().unwrap();
+    world_state.cities[next_city].owner = Some(world_state.get_current_player_index());
+    world_state.ownership_changes += 1;
     app_state.selection.last_army_city_selection = Some(next_city);
 }
 
+// The current player's pick of whatever is still to be handed out
+pub fn draft(app_state: &mut AppState, city_index: usize) {
+    let world_state = &mut app_state.world_state;
+    world_state.cities_to_assign.retain(|other| *other != city_index);
+    world_state.cities[city_index].owner = Some(world_state.get_current_player_index());
+    world_state.ownership_changes += 1;
+    app_state.selection.last_army_city_selection = Some(city_index);
+}
+
 pub fn randomising(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
     skia.set_matrix(&app_state.gfx);
 
@@ -26,10 +36,15 @@ pub fn randomising(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
     paint_title.set_anti_alias(true);
     paint_title.set_style(PaintStyle::StrokeAndFill);
     paint_title.set_color(Color::YELLOW);
-    skia.write_text_centre(30.0, &paint_title, "Assigning Cities", Point::new(l, rr.top), w, &FontFamily::EbGaramond);
+    let title = if app_state.world_state.mode == GameMode::Draft { "Drafting Cities" } else { "Assigning Cities" };
+    skia.write_text_centre(30.0, &paint_title, title, Point::new(l, rr.top), w, &FontFamily::EbGaramond);
 
-    // Name and territory
-    if let Some(city) = &app_state.selection.last_army_city_selection {
+    // Name and territory, of the city under the mouse while a human is picking
+    let picking =
+        app_state.world_state.mode == GameMode::Draft && app_state.world_state.get_current_player().is_human();
+    let shown = if picking { app_state.selection.last_city_hover } else { None };
+    if let Some(city_index) = shown.or(app_state.selection.last_army_city_selection) {
+        let city = &app_state.world_fixed.cities[city_index];
         let mut paint_left = Paint::default();
         paint_left.set_anti_alias(true);
         paint_left.set_style(PaintStyle::StrokeAndFill);
@@ -51,7 +66,7 @@ pub fn randomising(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
         skia.write_text(
             20.0,
             &paint_right,
-            &city.borrow().statics.borrow().name,
+            &city.name,
             Point::new(text_x, rr.top() + 60.0),
             text_w,
             &FontFamily::EbGaramond,
@@ -69,7 +84,7 @@ pub fn randomising(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
         skia.write_text(
             20.0,
             &paint_right,
-            &city.borrow().statics.borrow().territory_name,
+            &city.territory_name,
             Point::new(text_x, rr.top + 85.0),
             text_w,
             &FontFamily::EbGaramond,
@@ -87,7 +102,7 @@ pub fn randomising(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
         skia.write_text(
             20.0,
             &paint_right,
-            &city.borrow().statics.borrow().name,
+            &city.name,
             Point::new(text_x, rr.top + 110.0),
             text_w,
             &FontFamily::EbGaramond,
@@ -1,11 +1,13 @@
 use crate::app_state::AppState;
 use crate::lib::skia::{FontFamily, Skia};
+use crate::model::world_state::Visibility;
 use skia_safe::paint::Style;
 use skia_safe::{Color, Paint, Point, RRect, Rect};
 use std::collections::{BTreeMap, HashMap};
 
 pub fn region_summary(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
     let world_fixed = &app_state.world_fixed;
+    let visibility = app_state.world_state.visibility(world_fixed, app_state.viewing_player());
     skia.set_matrix(&app_state.gfx);
 
     // Paints
@@ -59,10 +61,13 @@ pub fn region_summary(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
             let msg = "No bonus".to_owned();
             skia.write_text(20.0, &paint_white, &msg, Point::new(rr.right - 128.0, y), 0.0, &FontFamily::EbGaramond);
 
-            // Work out proportions of ownership
+            // Work out proportions of ownership, leaving a gap for cities we can't see
             let mut map = HashMap::new();
-            for city in &territory.1.cities {
-                let owner = city.borrow().owner.unwrap();
+            for city_index in &territory.1.cities {
+                if visibility[*city_index] == Visibility::Hidden {
+                    continue;
+                }
+                let owner = app_state.world_state.cities[*city_index].owner.unwrap();
                 map.entry(owner).and_modify(|v| *v += 1.0f32).or_insert(1.0f32);
                 //                map.entry(owner.clone()).and_modify(|v| *v += 1).or_insert(1);
             }
@@ -82,7 +87,7 @@ pub fn region_summary(skia: &mut Skia, app_state: &mut AppState, rr: Rect) {
             paint_player.set_style(Style::Stroke);
             paint_player.set_stroke_width(4.0);
             for entry in prop {
-                paint_player.set_color(app_state.world_state.get_player_for_index(entry.0).borrow().colours[0]);
+                paint_player.set_color(world_fixed.players[entry.0].colours[0]);
                 skia.get_canvas().draw_line(Point::new(xx, yy), Point::new(xx + entry.1, yy), &paint_player);
                 xx += entry.1;
             }
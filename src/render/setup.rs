@@ -0,0 +1,155 @@
+use crate::app_state::{AppState, GFXState, NOISE_MIX};
+use crate::lib::skia::{FontFamily, Skia};
+use crate::model::setup::{GameSetup, SetupField, MAXIMUM_PLAYERS, PLAYER_COLOURS, SETUP_OPTIONS};
+use skia_safe::paint::Style;
+use skia_safe::{Color, Contains, Paint, Point, Rect, Vector};
+
+const WIDTH: f32 = 840.0;
+const HEIGHT: f32 = 650.0;
+const ROW_HEIGHT: f32 = 30.0;
+const PLAYERS_TOP: f32 = 90.0;
+const LABEL_WIDTH: f32 = 240.0;
+const VALUE_WIDTH: f32 = 260.0;
+
+pub fn setup_panel_rect(gfx: &GFXState) -> Rect {
+    Rect::from_xywh(gfx.half_width as f32 - WIDTH / 2.0, gfx.half_height as f32 - HEIGHT / 2.0, WIDTH, HEIGHT)
+}
+
+// Left edge and width of each column in the player list
+fn player_column(field: SetupField) -> (f32, f32) {
+    match field {
+        SetupField::Colour(_) => (0.0, 30.0),
+        SetupField::Name(_) => (40.0, 290.0),
+        SetupField::Human(_) => (340.0, 120.0),
+        SetupField::Difficulty(_) => (470.0, 110.0),
+        _ => (590.0, 190.0),
+    }
+}
+
+pub fn setup_field_rect(gfx: &GFXState, field: SetupField) -> Rect {
+    let rr = setup_panel_rect(gfx);
+    let left = rr.left + 30.0;
+    match field {
+        SetupField::Colour(index)
+        | SetupField::Name(index)
+        | SetupField::Human(index)
+        | SetupField::Difficulty(index)
+        | SetupField::Personality(index) => {
+            let (x, width) = player_column(field);
+            Rect::from_xywh(left + x, rr.top + PLAYERS_TOP + ROW_HEIGHT * index as f32, width, ROW_HEIGHT - 4.0)
+        }
+        _ => {
+            let index = SETUP_OPTIONS.iter().position(|option| *option == field).unwrap();
+            let top = rr.top + PLAYERS_TOP + ROW_HEIGHT * (MAXIMUM_PLAYERS + 1) as f32;
+            Rect::from_xywh(left + LABEL_WIDTH, top + ROW_HEIGHT * index as f32, VALUE_WIDTH, ROW_HEIGHT - 4.0)
+        }
+    }
+}
+
+pub fn setup_field_at(gfx: &GFXState, setup: &GameSetup, point: Point) -> Option<SetupField> {
+    setup.fields().into_iter().find(|field| setup_field_rect(gfx, *field).contains(point))
+}
+
+pub fn start_button_position(gfx: &GFXState) -> Vector {
+    let rr = setup_panel_rect(gfx);
+    Vector::new(gfx.half_width as f32, rr.bottom - 90.0)
+}
+
+// Everything about the game to come, each value clicked to change it
+pub fn render_setup(skia: &mut Skia, app_state: &AppState) {
+    let rr = setup_panel_rect(&app_state.gfx);
+    let setup = &app_state.setup;
+    skia.set_matrix(&app_state.gfx);
+
+    // Panel
+    let mut paint_background = Paint::default();
+    paint_background.set_anti_alias(true);
+    paint_background.set_style(Style::Fill);
+    paint_background.set_shader(skia.create_noise_shader(skia.colour_background, NOISE_MIX));
+    skia.get_canvas().draw_round_rect(rr, 32.0, 32.0, &paint_background);
+    let mut paint_border = Paint::default();
+    paint_border.set_anti_alias(true);
+    paint_border.set_style(Style::Stroke);
+    paint_border.set_stroke_width(1.0);
+    paint_border.set_color(skia.colour_outline);
+    skia.get_canvas().draw_round_rect(rr, 32.0, 32.0, &paint_border);
+
+    // Paints
+    let mut paint_title = Paint::default();
+    paint_title.set_anti_alias(true);
+    paint_title.set_style(Style::StrokeAndFill);
+    paint_title.set_color(Color::YELLOW);
+    let mut paint_label = Paint::default();
+    paint_label.set_anti_alias(true);
+    paint_label.set_style(Style::StrokeAndFill);
+    paint_label.set_color(Color::LIGHT_GRAY);
+    let mut paint_value = paint_label.clone();
+    paint_value.set_color(Color::WHITE);
+    let mut paint_hover = Paint::default();
+    paint_hover.set_anti_alias(true);
+    paint_hover.set_style(Style::Fill);
+    paint_hover.set_color(skia.colour_popup);
+
+    skia.write_text_centre(
+        30.0,
+        &paint_title,
+        "New Game",
+        Point::new(rr.left, rr.top + 20.0),
+        WIDTH,
+        &FontFamily::EbGaramond,
+    );
+
+    // Column headings over the player list
+    for field in [SetupField::Name(0), SetupField::Human(0), SetupField::Difficulty(0), SetupField::Personality(0)] {
+        let rect = setup_field_rect(&app_state.gfx, field);
+        skia.write_text(
+            18.0,
+            &paint_label,
+            GameSetup::label(field),
+            Point::new(rect.left + 6.0, rect.top - ROW_HEIGHT),
+            rect.width(),
+            &FontFamily::EbGaramond,
+        );
+    }
+
+    // Labels for the options that apply
+    for field in setup.fields().into_iter().filter(|field| SETUP_OPTIONS.contains(field)) {
+        let rect = setup_field_rect(&app_state.gfx, field);
+        skia.write_text_right(
+            20.0,
+            &paint_label,
+            &format!("{}:  ", GameSetup::label(field)),
+            Point::new(rect.left - LABEL_WIDTH, rect.top),
+            LABEL_WIDTH,
+            &FontFamily::EbGaramond,
+        );
+    }
+
+    // Values, highlighted under the mouse
+    for field in setup.fields() {
+        let rect = setup_field_rect(&app_state.gfx, field);
+        if rect.contains(app_state.hover) {
+            skia.get_canvas().draw_round_rect(rect, 4.0, 4.0, &paint_hover);
+        }
+        if let SetupField::Colour(index) = field {
+            let mut paint_player = Paint::default();
+            paint_player.set_anti_alias(true);
+            paint_player.set_style(Style::Fill);
+            paint_player.set_color(PLAYER_COLOURS[setup.players[index].colour][0]);
+            skia.get_canvas().draw_circle(rect.center(), 8.0, &paint_player);
+            skia.get_canvas().draw_circle(rect.center(), 10.0, &paint_border);
+            continue;
+        }
+        skia.write_text(
+            20.0,
+            &paint_value,
+            &setup.value(field),
+            Point::new(rect.left + 6.0, rect.top),
+            rect.width(),
+            &FontFamily::EbGaramond,
+        );
+    }
+
+    skia.button("Start", app_state, start_button_position(&app_state.gfx));
+    skia.get_canvas().restore();
+}
@@ -1,10 +1,11 @@
 use crate::app_state::{AppState, GameMode};
-use crate::lib::skia::{FontFamily, Skia};
+use crate::lib::skia::{FontFamily, Skia, ELLIPSIS};
 use skia_safe::{Color, Paint, PaintStyle, Point};
 
 pub fn render_title_bar(skia: &mut Skia, app_state: &mut AppState) {
     let world_state = &app_state.world_state;
-    let player = world_state.current_player.as_ref();
+    let player = world_state.get_current_player();
+    let player_static = &app_state.world_fixed.players[player.index];
     skia.set_matrix(&app_state.gfx);
 
     // Show faction name
@@ -17,18 +18,26 @@ pub fn render_title_bar(skia: &mut Skia, app_state: &mut AppState) {
 
     // Mode
     let phase = match world_state.mode {
+        GameMode::Setup => "New Game",
         GameMode::Randomising => "Assigning Cities",
+        GameMode::Draft => {
+            if player.is_human() {
+                "Draft a City"
+            } else {
+                "Computer Drafting"
+            }
+        }
         GameMode::ArmyPlacement => {
-            if player.unwrap().borrow().is_human() {
+            if player.is_human() {
                 "Initial Army Placement"
             } else {
                 "Computer Turn"
             }
         }
         GameMode::Game => {
-            if player.is_none() {
-                "No turn"
-            } else if player.unwrap().borrow().is_human() {
+            if player.is_human() && world_state.can_place_army() {
+                "Place Reinforcements"
+            } else if player.is_human() {
                 "Player Turn"
             } else {
                 "Computer Turn"
@@ -36,44 +45,53 @@ pub fn render_title_bar(skia: &mut Skia, app_state: &mut AppState) {
         }
         GameMode::End => "Game over",
     };
+    // Let the player know the computer is still working it out
+    let phase = match app_state.ai_turn.progress() {
+        Some((nodes, elapsed)) => {
+            format!("Computer thinking{} {} positions, {:.1}s", ELLIPSIS, nodes, elapsed.as_secs_f32())
+        }
+        None => phase.to_string(),
+    };
     skia.write_text_centre(
         30.0,
         &paint_title,
-        phase,
+        &phase,
         Point::new(0.0, 0.0),
         app_state.gfx.width as f32,
         &FontFamily::EbGaramond,
     );
-    if player.is_some() {
-        paint_title.set_color(player.unwrap().borrow().colours[0]);
-        skia.write_text(
-            20.0,
-            &paint_title,
-            &player.unwrap().borrow().name,
-            Point::new(160.0, 0.0),
-            app_state.gfx.width as f32,
-            &FontFamily::EbGaramond,
-        );
-
-        // City/territory count
-        {
-            let player = player.unwrap().borrow();
+    paint_title.set_color(player_static.colours[0]);
+    let name = if player.is_human() {
+        player_static.name.clone()
+    } else {
+        format!("{} ({}, {})", player_static.name, player.profile.personality.name(), player.profile.difficulty.name())
+    };
+    skia.write_text(
+        20.0,
+        &paint_title,
+        &name,
+        Point::new(160.0, 0.0),
+        app_state.gfx.width as f32,
+        &FontFamily::EbGaramond,
+    );
 
-            let mut count = 0;
-            for city in world_state.cities.iter() {
-                if city.borrow().owner.is_some() && city.borrow().owner.unwrap() == player.index {
-                    count += 1;
-                }
-            }
-            skia.write_text_right(
+    // City/territory count
+    {
+        let count = world_state.cities.iter().filter(|city| city.owner == Some(player.index)).count();
+        skia.write_text_right(
                 20.0,
                 &paint_title,
-                &format!("Score: {} Cities: {} of {}", player.score, count, world_state.cities.len(),),
+                &format!(
+                    "Score: {} Gold: {} Cities: {} of {}",
+                    player.score,
+                    player.gold,
+                    count,
+                    world_state.cities.len(),
+                ),
                 Point::new(0.0, 0.0),
                 app_state.gfx.width as f32 - 160.0,
                 &FontFamily::EbGaramond,
             );
-        }
     }
 
     skia.get_canvas().restore();
@@ -0,0 +1,31 @@
+use crate::app_state::{AppState, GFXState};
+use crate::input::{can_confirm, has_turn_controls};
+use crate::lib::skia::Skia;
+use crate::render::lower_panel::lower_panel_rect;
+use skia_safe::Vector;
+
+// Stacked to the right of the lower panel
+pub fn undo_button_position(gfx: &GFXState) -> Vector {
+    let rr = lower_panel_rect(gfx);
+    Vector::new(rr.right + 90.0, rr.top + 10.0)
+}
+
+pub fn confirm_button_position(gfx: &GFXState) -> Vector {
+    let rr = lower_panel_rect(gfx);
+    Vector::new(rr.right + 90.0, rr.top + 80.0)
+}
+
+// Undo while there's something to take back, confirm to finish the go once nothing's left to place
+pub fn turn_controls(skia: &mut Skia, app_state: &AppState) {
+    if !app_state.world_state.get_current_player().is_human() || !has_turn_controls(&app_state.world_state.mode) {
+        return;
+    }
+    skia.set_matrix(&app_state.gfx);
+    if app_state.history.can_undo() {
+        skia.button("Undo", app_state, undo_button_position(&app_state.gfx));
+    }
+    if can_confirm(&app_state.world_state) {
+        skia.button("Confirm", app_state, confirm_button_position(&app_state.gfx));
+    }
+    skia.get_canvas().restore();
+}